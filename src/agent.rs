@@ -0,0 +1,415 @@
+use anyhow::{Context, Result, Ok};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::budget;
+use crate::constants::open_router::SUMMARY_MODEL;
+use crate::context::{self, ContextOptions};
+use crate::cost;
+use crate::dedup::DedupOptions;
+use crate::exa_client::{SearchSource, fetch_exa_search_results, fetch_exa_similar_results, fetch_exa_url_contents};
+use crate::fetcher::{FetchMode, PageFetcher};
+use crate::generation::GenerationOptions;
+use crate::messages::msg;
+use crate::models::{ExaSearchOptions, FunctionDefinition, Message, ToolCall, ToolDefinition, UsageInfo};
+use crate::openrouter_client::generate_agent_step;
+use crate::output;
+use crate::prompts::{PromptName, Prompts};
+
+// Agent mode: instead of the fixed keyword -> search -> answer pipeline, the answer model is
+// offered search tools backed by Exa and decides itself what to look up, for a limited number
+// of steps. Every page a tool returns is numbered once, across all steps, so citations stay valid.
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentOptions {
+    // Use the agent instead of the keyword phase for plain searches.
+    pub enabled: bool,
+    // Model requests, including the final answer. The last one may not call tools.
+    pub max_steps: usize,
+    // Context tokens for the pages returned by one tool call.
+    pub tool_result_tokens: usize,
+}
+
+impl Default for AgentOptions {
+    fn default() -> Self {
+        Self { enabled: false, max_steps: 6, tool_result_tokens: 3000 }
+    }
+}
+
+// Pages per fetch_contents call; more would blow the tool result budget anyway.
+const MAX_FETCH_URLS: usize = 5;
+
+const STEP_LIMIT_PROMPT: &str =
+    "You have reached the step limit. Answer the question now from the results above, without calling any more tools.";
+
+fn tool_definitions() -> Vec<ToolDefinition> {
+    let function = |name, description, parameters| ToolDefinition {
+        kind: "function",
+        function: FunctionDefinition { name, description, parameters },
+    };
+    vec![
+        function("web_search", "Search the web. Returns the most relevant pages with their text.", json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "description": "Search keywords or a natural-language description of the pages to find" }
+            },
+            "required": ["query"],
+        })),
+        function("fetch_contents", "Read pages by URL, e.g. ones mentioned in earlier results.", json!({
+            "type": "object",
+            "properties": {
+                "urls": { "type": "array", "items": { "type": "string" }, "maxItems": MAX_FETCH_URLS, "description": "Page URLs" }
+            },
+            "required": ["urls"],
+        })),
+        function("find_similar", "Find pages similar to a page, e.g. other coverage of the same story.", json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "description": "URL of the page to find similar pages for" }
+            },
+            "required": ["url"],
+        })),
+    ]
+}
+
+#[derive(Deserialize)]
+struct WebSearchArgs {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct FetchContentsArgs {
+    urls: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FindSimilarArgs {
+    url: String,
+}
+
+// What the tools search and fetch with.
+pub struct Tools<'a> {
+    pub http_client: &'a Client,
+    pub exa_api_key: &'a str,
+    pub search_options: &'a ExaSearchOptions,
+    pub context_options: &'a ContextOptions,
+    pub dedup_options: &'a DedupOptions,
+    pub fetcher: &'a PageFetcher<'a>,
+}
+
+impl Tools<'_> {
+    // Runs one tool call. Exa errors are returned too; the model gets them as the tool's result.
+    async fn call(&self, tool_call: &ToolCall, user_query: &str) -> Result<Vec<SearchSource>> {
+        let arguments = match tool_call.function.arguments.trim() {
+            "" => "{}",
+            arguments => arguments,
+        };
+        let parse_error = |e: serde_json::Error| anyhow::anyhow!("Invalid arguments for `{}`: {}", tool_call.function.name, e);
+        // Highlights and summaries are focused on the user's question rather than each search.
+        let content_options = self.context_options.exa_contents(user_query);
+        let exa_contents = (self.fetcher.mode() != FetchMode::Primary).then_some(&content_options);
+        let mut sources = match tool_call.function.name.as_str() {
+            "web_search" => {
+                let args: WebSearchArgs = serde_json::from_str(arguments).map_err(parse_error)?;
+                fetch_exa_search_results(self.http_client, self.exa_api_key, &[args.query], self.search_options, exa_contents, self.dedup_options).await?
+            }
+            "fetch_contents" => {
+                let args: FetchContentsArgs = serde_json::from_str(arguments).map_err(parse_error)?;
+                if args.urls.is_empty() {
+                    return Err(anyhow::anyhow!("`fetch_contents` needs at least one URL"));
+                }
+                for url in &args.urls {
+                    reqwest::Url::parse(url).map_err(|_| anyhow::anyhow!("Not a valid URL: {}", url))?;
+                }
+                let urls: Vec<String> = args.urls.into_iter().take(MAX_FETCH_URLS).collect();
+                if self.fetcher.mode() == FetchMode::Primary {
+                    urls.iter().map(|url| SearchSource::from_url(url)).collect()
+                } else {
+                    fetch_exa_url_contents(self.http_client, self.exa_api_key, &urls, &content_options).await?
+                }
+            }
+            "find_similar" => {
+                let args: FindSimilarArgs = serde_json::from_str(arguments).map_err(parse_error)?;
+                reqwest::Url::parse(&args.url).map_err(|_| anyhow::anyhow!("Not a valid URL: {}", args.url))?;
+                fetch_exa_similar_results(self.http_client, self.exa_api_key, &args.url, self.search_options, exa_contents, self.dedup_options).await?
+            }
+            name => return Err(anyhow::anyhow!("Unknown tool `{}`. Available: web_search, fetch_contents, find_similar", name)),
+        };
+        self.fetcher.complete_sources(&mut sources).await;
+        Ok(sources)
+    }
+}
+
+// The conversation, owned, and lent out as request messages for each step.
+struct Turn {
+    role: &'static str,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    tool_call_id: Option<String>,
+}
+
+impl Turn {
+    fn new(role: &'static str, content: String) -> Self {
+        Self { role, content, tool_calls: Vec::new(), tool_call_id: None }
+    }
+
+    fn message(&self) -> Message<'_> {
+        Message {
+            role: self.role,
+            content: &self.content,
+            tool_calls: (!self.tool_calls.is_empty()).then_some(self.tool_calls.as_slice()),
+            tool_call_id: self.tool_call_id.as_deref(),
+        }
+    }
+}
+
+pub struct AgentAnswer {
+    pub answer: String,
+    // Every page the tools returned, in citation order.
+    pub sources: Vec<SearchSource>,
+    // Summed over all steps.
+    pub usage: Option<UsageInfo>,
+}
+
+// Runs the tool loop until the model answers or the step limit is reached. Only the final
+// answer is written to stdout; each tool call is traced on stderr, and text the model writes
+// alongside its tool calls is shown there with --verbose.
+pub async fn run(
+    tools: &Tools<'_>,
+    openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
+    options: &AgentOptions,
+    user_query: &str,
+) -> Result<AgentAnswer> {
+    if options.max_steps == 0 {
+        return Err(anyhow::anyhow!("The agent's `max_steps` (--max-steps) must be at least 1"));
+    }
+    if let Some(capabilities) = cost::capabilities(SUMMARY_MODEL)
+        && !capabilities.supported_parameters.is_empty()
+        && !capabilities.supported_parameters.iter().any(|name| name == "tools")
+    {
+        output::warn(msg!("agent.no_tools", model = SUMMARY_MODEL).yellow());
+    }
+
+    let definitions = tool_definitions();
+    let mut result_options = tools.context_options.clone();
+    result_options.token_budget = options.tool_result_tokens;
+
+    let mut turns = Vec::new();
+    if let Some(system) = prompts.system() {
+        turns.push(Turn::new("system", system.to_string()));
+    }
+    turns.push(Turn::new("user", prompts.render(PromptName::Agent, &prompts.vars(user_query))?));
+    let mut sources: Vec<SearchSource> = Vec::new();
+    let mut usage: Option<UsageInfo> = None;
+
+    for step in 1..=options.max_steps {
+        let last_step = step == options.max_steps;
+        if last_step && step > 1 {
+            output::warn(msg!("agent.step_limit", steps = options.max_steps).yellow());
+            turns.push(Turn::new("user", STEP_LIMIT_PROMPT.to_string()));
+        }
+        output::status(msg!("agent.step", step = step, max = options.max_steps).bright_blue());
+
+        let conversation: String = turns.iter().map(|turn| turn.content.as_str()).collect::<Vec<_>>().join("\n");
+        let model = budget::admit(SUMMARY_MODEL, "OpenRouter Agent Step", None, &conversation)?;
+        let messages = turns.iter().map(Turn::message).collect();
+        let tool_choice = if last_step { "none" } else { "auto" };
+        let response = generate_agent_step(
+            tools.http_client, openrouter_api_key, &model, generation.phase(PromptName::Agent), messages, &definitions, tool_choice,
+        ).await?;
+        if let Some(step_usage) = &response.usage {
+            usage.get_or_insert_with(UsageInfo::default).add(step_usage);
+        }
+
+        if response.tool_calls.is_empty() || last_step {
+            if response.content.trim().is_empty() {
+                return Err(anyhow::anyhow!("{}", msg!("agent.no_answer", steps = step)));
+            }
+            output::status(msg!("answer.streaming").bright_green().bold());
+            output::answer(&response.content).context("Failed to write answer to stdout")?;
+            return Ok(AgentAnswer { answer: response.content, sources, usage });
+        }
+        if !response.content.trim().is_empty() {
+            output::detail(msg!("agent.notes", text = response.content.trim()).dimmed());
+        }
+
+        let mut assistant = Turn::new("assistant", response.content);
+        assistant.tool_calls = response.tool_calls.clone();
+        turns.push(assistant);
+        for tool_call in response.tool_calls {
+            output::status(msg!("agent.call", tool = tool_call.function.name, arguments = tool_call.function.arguments).cyan());
+            let result = match tools.call(&tool_call, user_query).await {
+                Result::Ok(found) => tool_result(&mut sources, found, &result_options),
+                Err(e) => {
+                    output::warn(msg!("agent.error", error = format!("{:#}", e)).yellow());
+                    format!("Error: {:#}", e)
+                }
+            };
+            let mut turn = Turn::new("tool", result);
+            turn.tool_call_id = Some(tool_call.id);
+            turns.push(turn);
+        }
+    }
+    unreachable!("the last step always returns")
+}
+
+// Adds the pages not seen before to `sources` and renders them for the model, numbered after
+// the earlier ones; pages already returned are referred to by their number.
+fn tool_result(sources: &mut Vec<SearchSource>, found: Vec<SearchSource>, options: &ContextOptions) -> String {
+    let first_number = sources.len() + 1;
+    let mut seen = Vec::new();
+    let mut new_sources = Vec::new();
+    for source in found {
+        match sources.iter().position(|s| s.url == source.url) {
+            Some(index) => seen.push(format!("Result {}", index + 1)),
+            None if !new_sources.iter().any(|s: &SearchSource| s.url == source.url) => new_sources.push(source),
+            None => {}
+        }
+    }
+    output::status(msg!("agent.result", count = new_sources.len(), seen = seen.len()).dimmed());
+
+    let mut result = if new_sources.is_empty() {
+        "No new pages.\n".to_string()
+    } else {
+        context::render_sources(&new_sources, options, first_number)
+    };
+    if !seen.is_empty() {
+        result.push_str(&format!("\nAlso returned, already shown: {}\n", seen.join(", ")));
+    }
+    sources.extend(new_sources);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::FetchOptions;
+    use crate::models::FunctionCall;
+
+    fn page(url: &str) -> SearchSource {
+        SearchSource {
+            title: format!("Title of {}", url),
+            text: Some(format!("Text of {}", url)),
+            ..SearchSource::from_url(url)
+        }
+    }
+
+    fn pages(urls: &[&str]) -> Vec<SearchSource> {
+        urls.iter().map(|url| page(url)).collect()
+    }
+
+    fn urls(sources: &[SearchSource]) -> Vec<&str> {
+        sources.iter().map(|source| source.url.as_str()).collect()
+    }
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall { name: name.to_string(), arguments: arguments.to_string() },
+        }
+    }
+
+    #[test]
+    fn results_are_numbered_across_steps() {
+        let options = ContextOptions::default();
+        let mut sources = Vec::new();
+
+        let first = tool_result(&mut sources, pages(&["https://a.example", "https://b.example"]), &options);
+        assert!(first.contains("Result 1:\nTitle: Title of https://a.example"), "{}", first);
+        assert!(first.contains("Result 2:\nTitle: Title of https://b.example"), "{}", first);
+        assert!(!first.contains("already shown"), "{}", first);
+
+        // New pages continue the numbering; pages seen before are referred to by their number.
+        let second = tool_result(&mut sources, pages(&["https://b.example", "https://c.example", "https://a.example"]), &options);
+        assert!(second.contains("Result 3:\nTitle: Title of https://c.example"), "{}", second);
+        assert!(!second.contains("Result 1:") && !second.contains("Result 2:"), "{}", second);
+        assert!(second.ends_with("\nAlso returned, already shown: Result 2, Result 1\n"), "{}", second);
+
+        assert_eq!(urls(&sources), ["https://a.example", "https://b.example", "https://c.example"]);
+    }
+
+    #[test]
+    fn repeats_within_one_result_count_once() {
+        let options = ContextOptions::default();
+        let mut sources = pages(&["https://a.example"]);
+        let result = tool_result(&mut sources, pages(&["https://b.example", "https://b.example", "https://a.example", "https://a.example"]), &options);
+        assert!(result.contains("Result 2:\nTitle: Title of https://b.example"), "{}", result);
+        assert!(!result.contains("Result 3:"), "{}", result);
+        assert!(result.ends_with("\nAlso returned, already shown: Result 1, Result 1\n"), "{}", result);
+        assert_eq!(urls(&sources), ["https://a.example", "https://b.example"]);
+    }
+
+    #[test]
+    fn nothing_new() {
+        let options = ContextOptions::default();
+        let mut sources = pages(&["https://a.example"]);
+        assert_eq!(tool_result(&mut sources, Vec::new(), &options), "No new pages.\n");
+        assert_eq!(
+            tool_result(&mut sources, pages(&["https://a.example"]), &options),
+            "No new pages.\n\nAlso returned, already shown: Result 1\n",
+        );
+        assert_eq!(sources.len(), 1);
+    }
+
+    #[test]
+    fn tool_definitions_name_their_required_arguments() {
+        let definitions = tool_definitions();
+        let required: Vec<(&str, &serde_json::Value)> = definitions.iter()
+            .map(|definition| (definition.function.name, &definition.function.parameters["required"]))
+            .collect();
+        assert_eq!(required, [
+            ("web_search", &json!(["query"])),
+            ("fetch_contents", &json!(["urls"])),
+            ("find_similar", &json!(["url"])),
+        ]);
+    }
+
+    #[test]
+    fn turns_only_send_tool_fields_when_set() {
+        let plain = Turn::new("user", "question".to_string());
+        let message = plain.message();
+        assert!(message.tool_calls.is_none() && message.tool_call_id.is_none());
+
+        let mut assistant = Turn::new("assistant", String::new());
+        assistant.tool_calls = vec![call("web_search", "{}")];
+        assert_eq!(assistant.message().tool_calls.map(<[ToolCall]>::len), Some(1));
+
+        let mut tool = Turn::new("tool", "result".to_string());
+        tool.tool_call_id = Some("call_1".to_string());
+        assert_eq!(tool.message().tool_call_id, Some("call_1"));
+    }
+
+    // Invalid calls are rejected before anything is sent, so these need no network.
+    #[tokio::test]
+    async fn invalid_calls_are_errors_for_the_model() {
+        let http_client = Client::new();
+        let fetch_options = FetchOptions::default();
+        let fetcher = PageFetcher::new(&http_client, &fetch_options);
+        let tools = Tools {
+            http_client: &http_client,
+            exa_api_key: "",
+            search_options: &ExaSearchOptions::default(),
+            context_options: &ContextOptions::default(),
+            dedup_options: &DedupOptions::default(),
+            fetcher: &fetcher,
+        };
+        let cases = [
+            (call("web_search", "{\"q\": \"rust\"}"), "Invalid arguments for `web_search`"),
+            (call("web_search", ""), "Invalid arguments for `web_search`"),
+            (call("fetch_contents", "{\"urls\": []}"), "needs at least one URL"),
+            (call("fetch_contents", "{\"urls\": [\"https://a.example\", \"not a url\"]}"), "Not a valid URL: not a url"),
+            (call("find_similar", "{\"url\": 42}"), "Invalid arguments for `find_similar`"),
+            (call("delete_everything", "{}"), "Unknown tool `delete_everything`"),
+        ];
+        for (tool_call, expected) in cases {
+            let error = tools.call(&tool_call, "question").await.err().map(|e| format!("{:#}", e)).unwrap_or_default();
+            assert!(error.contains(expected), "{:?}: {}", tool_call.function, error);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::constants::open_router::SEARCH_MODEL;
+use crate::context::estimate_tokens;
+use crate::cost::{self, DaySpend, SpendLedger, format_usd};
+use crate::messages::msg;
+use crate::output;
+
+// Token and dollar limits per run and per day, checked before each OpenRouter request from the
+// estimated prompt size plus an allowance for the response, and before each Exa request from
+// what has already been spent.
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnExceed {
+    // Stop with an error naming the limit.
+    Abort,
+    // Answer from fewer sources, then switch to the degrade model, before giving up.
+    Degrade,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetOptions {
+    pub max_run_tokens: Option<u64>,
+    pub max_run_usd: Option<f64>,
+    // Day limits include earlier runs recorded in the spend ledger.
+    pub max_day_tokens: Option<u64>,
+    pub max_day_usd: Option<f64>,
+    // Response tokens assumed for each request when checking it.
+    pub completion_tokens: u64,
+    pub on_exceed: OnExceed,
+    // Model used instead when a request does not fit. Defaults to the keyword model, so keyword
+    // requests (and anything already sent to that model) can't be degraded without setting it.
+    pub degrade_model: Option<String>,
+    // Fewest sources the answer is trimmed down to before switching models.
+    pub min_sources: usize,
+}
+
+impl Default for BudgetOptions {
+    fn default() -> Self {
+        Self {
+            max_run_tokens: None,
+            max_run_usd: None,
+            max_day_tokens: None,
+            max_day_usd: None,
+            completion_tokens: 1024,
+            on_exceed: OnExceed::Abort,
+            degrade_model: None,
+            min_sources: 2,
+        }
+    }
+}
+
+impl BudgetOptions {
+    fn is_limited(&self) -> bool {
+        self.max_run_tokens.is_some() || self.max_run_usd.is_some() || self.max_day_tokens.is_some() || self.max_day_usd.is_some()
+    }
+
+    fn degrade_model(&self) -> &str {
+        self.degrade_model.as_deref().unwrap_or(SEARCH_MODEL)
+    }
+}
+
+struct Budget {
+    options: BudgetOptions,
+    // Today's spend before this run.
+    earlier_today: DaySpend,
+}
+
+static BUDGET: OnceLock<Budget> = OnceLock::new();
+
+// Reads today's earlier spend when a day limit is set; an unreadable ledger counts as nothing spent.
+pub fn init(options: &BudgetOptions) {
+    let earlier_today = if options.max_day_tokens.is_some() || options.max_day_usd.is_some() {
+        SpendLedger::load()
+            .inspect_err(|e| output::warn(msg!("cost.ledger_read_failed", error = format!("{:#}", e)).yellow()))
+            .map(|ledger| ledger.today())
+            .unwrap_or_default()
+    } else {
+        DaySpend::default()
+    };
+    let _ = BUDGET.set(Budget { options: options.clone(), earlier_today });
+}
+
+fn budget() -> Option<&'static Budget> {
+    BUDGET.get().filter(|budget| budget.options.is_limited())
+}
+
+pub fn degrading() -> bool {
+    budget().is_some_and(|budget| budget.options.on_exceed == OnExceed::Degrade)
+}
+
+pub fn min_sources() -> usize {
+    budget().map_or(1, |budget| budget.options.min_sources.max(1))
+}
+
+// Tokens a request is expected to use: its messages plus the response allowance.
+pub fn estimate_request(system: Option<&str>, prompt: &str) -> u64 {
+    let completion_tokens = budget().map_or(0, |budget| budget.options.completion_tokens);
+    (system.map_or(0, estimate_tokens) + estimate_tokens(prompt)) as u64 + completion_tokens
+}
+
+// Splits an estimate from estimate_request back into prompt and response tokens to price it.
+fn estimate_usd(options: &BudgetOptions, model: &str, tokens: u64) -> Option<f64> {
+    let completion_tokens = options.completion_tokens.min(tokens);
+    cost::estimate(model, tokens - completion_tokens, completion_tokens)
+}
+
+// The limit a request of `tokens` would go over, described with what is left of it.
+fn exceeded(model: &str, tokens: u64) -> Option<String> {
+    let budget = budget()?;
+    let options = &budget.options;
+    let run = cost::run_cost();
+    // A model without a known price can't be checked against the dollar limits.
+    let usd = estimate_usd(options, model, tokens).unwrap_or(0.0);
+    let day_tokens = budget.earlier_today.total_tokens() + run.total_tokens();
+    let day_usd = budget.earlier_today.total_usd() + run.total_usd();
+
+    if let Some(limit) = options.max_run_tokens && run.total_tokens() + tokens > limit {
+        return Some(msg!("budget.run_tokens", left = limit.saturating_sub(run.total_tokens()), limit = limit));
+    }
+    if let Some(limit) = options.max_day_tokens && day_tokens + tokens > limit {
+        return Some(msg!("budget.day_tokens", left = limit.saturating_sub(day_tokens), limit = limit));
+    }
+    if let Some(limit) = options.max_run_usd && run.total_usd() + usd > limit {
+        return Some(msg!("budget.run_usd", left = format_usd((limit - run.total_usd()).max(0.0)), limit = format_usd(limit)));
+    }
+    if let Some(limit) = options.max_day_usd && day_usd + usd > limit {
+        return Some(msg!("budget.day_usd", left = format_usd((limit - day_usd).max(0.0)), limit = format_usd(limit)));
+    }
+    None
+}
+
+pub fn fits(model: &str, tokens: u64) -> bool {
+    exceeded(model, tokens).is_none()
+}
+
+// The model to send an OpenRouter request to: `model` when the request fits the budget, the
+// degrade model when degrading and that one fits. Errors otherwise.
+pub fn admit(model: &str, request: &str, system: Option<&str>, prompt: &str) -> Result<String> {
+    let Some(budget) = budget() else { return Ok(model.to_string()) };
+    let tokens = estimate_request(system, prompt);
+    let Some(limit) = exceeded(model, tokens) else { return Ok(model.to_string()) };
+
+    let fallback = budget.options.degrade_model();
+    if budget.options.on_exceed == OnExceed::Degrade {
+        if fallback == model {
+            output::warn(msg!("budget.degrade_same_model", request = request, model = model).yellow());
+        } else if fits(fallback, tokens) {
+            output::warn(msg!("budget.degraded_model", request = request, limit = limit, model = fallback).yellow());
+            return Ok(fallback.to_string());
+        }
+    }
+    let usd = estimate_usd(&budget.options, model, tokens)
+        .map_or_else(|| msg!("budget.unknown_price"), format_usd);
+    let message = match budget.options.on_exceed {
+        OnExceed::Abort => msg!("budget.abort", request = request, tokens = tokens, cost = usd, limit = limit),
+        OnExceed::Degrade => msg!("budget.abort_degraded", request = request, tokens = tokens, cost = usd, limit = limit),
+    };
+    Err(anyhow::anyhow!("{}", message))
+}
+
+// Errors once a limit is used up, before a request whose cost isn't known upfront.
+pub fn check_spent(request: &str) -> Result<()> {
+    match exceeded("", 0) {
+        Some(limit) => Err(anyhow::anyhow!("{}", msg!("budget.spent", request = request, limit = limit))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,689 @@
+use clap::{ArgAction, Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::agent::AgentOptions;
+use crate::budget::{BudgetOptions, OnExceed};
+use crate::config::{parse_end_published_date, parse_published_date};
+use crate::context::{ContentMode, ContextOptions};
+use crate::fetcher::{FetchMode, FetchOptions};
+use crate::generation::{GenerationOptions, GenerationSetting, parse_setting};
+use crate::kb::{KbMode, KbOptions};
+use crate::language::LanguageOptions;
+use crate::messages::UiLanguage;
+use crate::models::{ExaCategory, ExaSearchOptions, ExaSearchType};
+use crate::output::{ColorChoice, Verbosity};
+use crate::persona::PersonaOptions;
+use crate::prompts::PromptName;
+use crate::provider::{DataCollection, ProviderPreferences, ProviderSort};
+use crate::reasoning::{ReasoningEffort, ReasoningOptions, ThinkingDisplay};
+use crate::rerank::RerankOptions;
+use crate::structured::StructuredOptions;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Answer questions with LLM-generated keywords, Exa web search and an OpenRouter summary")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(after_help = "A question whose first word is a subcommand name (search, similar, summarize, index, kb, prompts, help) \
+is read as that subcommand. Put `search` or `--` in front of it: `search index funds 2025` or `-- similar products to notion`.")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// The question to research. Read from stdin when omitted. A question starting with a
+    /// subcommand name (e.g. "index funds 2025") needs `search` or `--` in front of it.
+    pub query: Vec<String>,
+
+    /// Only print the final answer (and errors).
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print extra diagnostics to stderr.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Print the answer as raw Markdown instead of rendering it for the terminal.
+    #[arg(long, global = true)]
+    pub raw: bool,
+
+    /// When to use colors. `auto` honors NO_COLOR, CLICOLOR_FORCE and TTY detection.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Language of the progress and error messages (defaults to your locale's).
+    #[arg(long, global = true, value_enum)]
+    pub ui_lang: Option<UiLanguage>,
+
+    /// Path to a TOML config file (defaults to ./llm-search.toml, then the user config dir).
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Apply a `[profiles.<name>]` section of the config file.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(flatten)]
+    pub search: SearchArgs,
+
+    #[command(flatten)]
+    pub context: ContextArgs,
+
+    #[command(flatten)]
+    pub fetch: FetchArgs,
+
+    #[command(flatten)]
+    pub rerank: RerankArgs,
+
+    #[command(flatten)]
+    pub kb: KbArgs,
+
+    #[command(flatten)]
+    pub answer: AnswerArgs,
+
+    #[command(flatten)]
+    pub persona: PersonaArgs,
+
+    #[command(flatten)]
+    pub language: LanguageArgs,
+
+    #[command(flatten)]
+    pub budget: BudgetArgs,
+
+    #[command(flatten)]
+    pub generation: GenerationArgs,
+
+    #[command(flatten)]
+    pub provider: ProviderArgs,
+
+    #[command(flatten)]
+    pub agent: AgentArgs,
+
+    #[command(flatten)]
+    pub structured: StructuredArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Research a question (the default). Use it when the question starts with a subcommand name.
+    Search {
+        /// The question to research. Read from stdin when omitted.
+        query: Vec<String>,
+    },
+
+    /// Find pages similar to a URL (Exa findSimilar), optionally answering a question over them.
+    Similar {
+        /// The article to start from.
+        url: String,
+
+        /// Question to answer using the similar pages. Without one, the pages are just listed.
+        question: Vec<String>,
+    },
+
+    /// Synthesize an answer from pages you already have, skipping keyword generation and search.
+    Summarize {
+        /// Pages to summarize. Read from --file or stdin when omitted.
+        urls: Vec<String>,
+
+        /// File with one URL per line (`-` for stdin). Blank lines and `#` comments are ignored.
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// What to ask about the pages (defaults to a general synthesis).
+        #[arg(long)]
+        question: Option<String>,
+    },
+
+    /// Manage the index of the local document corpus searched alongside the web.
+    Index {
+        #[command(subcommand)]
+        action: IndexCommand,
+    },
+
+    /// Search the knowledge base of pages saved by earlier runs.
+    Kb {
+        #[command(subcommand)]
+        action: KbCommand,
+    },
+
+    /// List, show or customize the prompt templates.
+    Prompts {
+        #[command(subcommand)]
+        action: PromptsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptsCommand {
+    /// List the prompts and whether each is built-in or overridden.
+    List,
+
+    /// Print the template text in effect for a prompt.
+    Show {
+        name: PromptName,
+
+        /// Print the built-in template even when it is overridden.
+        #[arg(long)]
+        default: bool,
+    },
+
+    /// Open a prompt's override file in $EDITOR, creating it from the built-in template first.
+    Edit {
+        name: PromptName,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KbCommand {
+    /// Find stored pages relevant to a question, with their URL and fetch date.
+    Query {
+        /// What to look up.
+        #[arg(required = true)]
+        question: Vec<String>,
+
+        /// Answer the question from the matching pages instead of listing them.
+        #[arg(long)]
+        answer: bool,
+
+        /// Maximum number of pages to return.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexCommand {
+    /// Index a directory of Markdown, text and HTML files from scratch.
+    Build {
+        /// Directory to index (defaults to `path` in the [corpus] config section).
+        dir: Option<PathBuf>,
+    },
+
+    /// Re-index only the files added, changed or removed since the last build.
+    Update,
+}
+
+// Exa search filters. Each flag overrides the matching `[exa]` value from the config file.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Search options")]
+pub struct SearchArgs {
+    /// Only include results published on or after this date (YYYY-MM-DD or RFC 3339).
+    #[arg(long, global = true, value_parser = parse_published_date)]
+    pub start_date: Option<String>,
+
+    /// Only include results published on or before this date (YYYY-MM-DD or RFC 3339).
+    #[arg(long, global = true, value_parser = parse_end_published_date)]
+    pub end_date: Option<String>,
+
+    /// Only search these domains (repeatable).
+    #[arg(long = "include-domain", global = true, value_name = "DOMAIN")]
+    pub include_domains: Vec<String>,
+
+    /// Never return results from these domains (repeatable).
+    #[arg(long = "exclude-domain", global = true, value_name = "DOMAIN")]
+    pub exclude_domains: Vec<String>,
+
+    /// Restrict results to an Exa content category.
+    #[arg(long, global = true, value_enum)]
+    pub category: Option<ExaCategory>,
+
+    /// Exa search type.
+    #[arg(long, global = true, value_enum)]
+    pub search_type: Option<ExaSearchType>,
+
+    /// Text that must appear in results (repeatable, up to 5 words each).
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub include_text: Vec<String>,
+
+    /// Text that must not appear in results (repeatable, up to 5 words each).
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub exclude_text: Vec<String>,
+
+    /// Ignore the recency and domain hints suggested during keyword generation.
+    #[arg(long, global = true)]
+    pub no_search_hints: bool,
+
+    /// Don't restrict the search to recent pages when the question asks for the latest news.
+    #[arg(long, global = true)]
+    pub no_auto_recency: bool,
+
+    /// Don't search the local document corpus.
+    #[arg(long, global = true)]
+    pub no_corpus: bool,
+
+    /// Keep near-duplicate results (mirrors, syndicated copies) instead of collapsing them.
+    #[arg(long, global = true)]
+    pub no_dedup: bool,
+}
+
+impl SearchArgs {
+    pub fn apply(&self, options: &mut ExaSearchOptions) {
+        if self.start_date.is_some() {
+            options.start_published_date = self.start_date.clone();
+        }
+        if self.end_date.is_some() {
+            options.end_published_date = self.end_date.clone();
+        }
+        if !self.include_domains.is_empty() {
+            options.include_domains = self.include_domains.clone();
+        }
+        if !self.exclude_domains.is_empty() {
+            options.exclude_domains = self.exclude_domains.clone();
+        }
+        if self.category.is_some() {
+            options.category = self.category;
+        }
+        if self.search_type.is_some() {
+            options.search_type = self.search_type;
+        }
+        if !self.include_text.is_empty() {
+            options.include_text = self.include_text.clone();
+        }
+        if !self.exclude_text.is_empty() {
+            options.exclude_text = self.exclude_text.clone();
+        }
+    }
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose > 0 {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+// How fetched pages are condensed into the prompt. Overrides the `[context]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Context options")]
+pub struct ContextArgs {
+    /// Which Exa content to put into the prompt.
+    #[arg(long, global = true, value_enum)]
+    pub content_mode: Option<ContentMode>,
+
+    /// Approximate token budget for all search results in the prompt.
+    #[arg(long, global = true, value_name = "TOKENS")]
+    pub context_budget: Option<usize>,
+
+    /// Sentences per Exa highlight.
+    #[arg(long, global = true, value_name = "N")]
+    pub highlight_sentences: Option<u32>,
+
+    /// Highlights requested per result.
+    #[arg(long, global = true, value_name = "N")]
+    pub highlights_per_url: Option<u32>,
+
+    /// Query used to pick highlights (defaults to your question).
+    #[arg(long, global = true, value_name = "QUERY")]
+    pub highlight_query: Option<String>,
+
+    /// Query used to guide Exa summaries (defaults to your question).
+    #[arg(long, global = true, value_name = "QUERY")]
+    pub summary_query: Option<String>,
+}
+
+impl ContextArgs {
+    pub fn apply(&self, options: &mut ContextOptions) {
+        if let Some(mode) = self.content_mode {
+            options.mode = mode;
+        }
+        if let Some(budget) = self.context_budget {
+            options.token_budget = budget;
+        }
+        if let Some(sentences) = self.highlight_sentences {
+            options.highlight_sentences = sentences;
+        }
+        if let Some(per_url) = self.highlights_per_url {
+            options.highlights_per_url = per_url;
+        }
+        if self.highlight_query.is_some() {
+            options.highlight_query = self.highlight_query.clone();
+        }
+        if self.summary_query.is_some() {
+            options.summary_query = self.summary_query.clone();
+        }
+    }
+}
+
+// Direct page downloads. Overrides the `[fetch]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Fetch options")]
+pub struct FetchArgs {
+    /// Download pages directly: never, only where Exa has no content, or instead of Exa /contents.
+    #[arg(long, global = true, value_enum)]
+    pub fetch_mode: Option<FetchMode>,
+
+    /// Largest page to download, in bytes.
+    #[arg(long, global = true, value_name = "BYTES")]
+    pub max_page_bytes: Option<usize>,
+}
+
+impl FetchArgs {
+    pub fn apply(&self, options: &mut FetchOptions) {
+        if let Some(mode) = self.fetch_mode {
+            options.mode = mode;
+        }
+        if let Some(max_bytes) = self.max_page_bytes {
+            options.max_bytes = max_bytes;
+        }
+    }
+}
+
+// Embedding-based reranking. Overrides the `[rerank]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Rerank options")]
+pub struct RerankArgs {
+    /// Reorder results by embedding similarity to the question before answering.
+    #[arg(long, global = true, overrides_with = "no_rerank")]
+    pub rerank: bool,
+
+    /// Use the results in the order the search returned them.
+    #[arg(long, global = true)]
+    pub no_rerank: bool,
+
+    /// Drop results whose cosine similarity to the question is below this.
+    #[arg(long, global = true, value_name = "SIMILARITY")]
+    pub rerank_threshold: Option<f32>,
+
+    /// Keep at most this many results after reranking.
+    #[arg(long, global = true, value_name = "N")]
+    pub rerank_max_results: Option<usize>,
+}
+
+impl RerankArgs {
+    pub fn apply(&self, options: &mut RerankOptions) {
+        if self.rerank {
+            options.enabled = true;
+        }
+        if self.no_rerank {
+            options.enabled = false;
+        }
+        if let Some(threshold) = self.rerank_threshold {
+            options.threshold = threshold;
+        }
+        if self.rerank_max_results.is_some() {
+            options.max_results = self.rerank_max_results;
+        }
+    }
+}
+
+// Knowledge base of fetched pages. Overrides the `[kb]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Knowledge base options")]
+pub struct KbArgs {
+    /// Save fetched pages, and whether to retrieve from saved pages alongside or instead of the web.
+    #[arg(long, global = true, value_enum)]
+    pub kb_mode: Option<KbMode>,
+
+    /// Minimum similarity for a saved page to be used.
+    #[arg(long, global = true, value_name = "SIMILARITY")]
+    pub kb_threshold: Option<f32>,
+}
+
+impl KbArgs {
+    pub fn apply(&self, options: &mut KbOptions) {
+        if let Some(mode) = self.kb_mode {
+            options.mode = mode;
+        }
+        if let Some(threshold) = self.kb_threshold {
+            options.threshold = threshold;
+        }
+    }
+}
+
+// Extra generation steps after the final answer.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Answer options")]
+pub struct AnswerArgs {
+    /// Suggest follow-up questions after the answer.
+    #[arg(long, global = true)]
+    pub follow_ups: bool,
+
+    /// Review the answer against the search results after writing it.
+    #[arg(long, global = true)]
+    pub critique: bool,
+}
+
+// System prompt for all model calls. Overrides the `[persona]` config section and the profile.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Persona options")]
+pub struct PersonaArgs {
+    /// Persona preset to answer as (see `prompts list`).
+    #[arg(long, global = true, value_name = "NAME")]
+    pub persona: Option<String>,
+
+    /// Extra system prompt text, sent after the persona's.
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub system: Option<String>,
+}
+
+impl PersonaArgs {
+    pub fn apply(&self, options: &mut PersonaOptions) {
+        if self.persona.is_some() {
+            options.name = self.persona.clone();
+        }
+        if self.system.is_some() {
+            options.system = self.system.clone();
+        }
+    }
+}
+
+// Keyword and answer languages. Overrides the `[language]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Language options")]
+pub struct LanguageArgs {
+    /// Write the answer in this language (a name, a tag such as zh-CN, or `query` for the question's language).
+    #[arg(long, global = true, value_name = "LANG")]
+    pub answer_lang: Option<String>,
+
+    /// Generate keywords in each of these languages and search with every set (e.g. query,en).
+    #[arg(long, global = true, value_name = "LANG", value_delimiter = ',')]
+    pub keyword_lang: Vec<String>,
+}
+
+impl LanguageArgs {
+    pub fn apply(&self, options: &mut LanguageOptions) {
+        if self.answer_lang.is_some() {
+            options.answer = self.answer_lang.clone();
+        }
+        if !self.keyword_lang.is_empty() {
+            options.keywords = self.keyword_lang.clone();
+        }
+    }
+}
+
+// Token and spending limits. Overrides the `[budget]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Budget options")]
+pub struct BudgetArgs {
+    /// Maximum tokens (prompt and response) this run may use.
+    #[arg(long, global = true, value_name = "TOKENS")]
+    pub max_run_tokens: Option<u64>,
+
+    /// Maximum dollars this run may spend.
+    #[arg(long, global = true, value_name = "USD")]
+    pub max_run_usd: Option<f64>,
+
+    /// Maximum tokens per day, counting earlier runs.
+    #[arg(long, global = true, value_name = "TOKENS")]
+    pub max_day_tokens: Option<u64>,
+
+    /// Maximum dollars per day, counting earlier runs.
+    #[arg(long, global = true, value_name = "USD")]
+    pub max_day_usd: Option<f64>,
+
+    /// What to do when a request would exceed a budget: abort, or degrade to fewer sources and a smaller model.
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    pub on_budget: Option<OnExceed>,
+
+    /// Model to switch to with --on-budget degrade (default: the keyword model).
+    #[arg(long, global = true, value_name = "MODEL")]
+    pub degrade_model: Option<String>,
+}
+
+impl BudgetArgs {
+    pub fn apply(&self, options: &mut BudgetOptions) {
+        if self.max_run_tokens.is_some() {
+            options.max_run_tokens = self.max_run_tokens;
+        }
+        if self.max_run_usd.is_some() {
+            options.max_run_usd = self.max_run_usd;
+        }
+        if self.max_day_tokens.is_some() {
+            options.max_day_tokens = self.max_day_tokens;
+        }
+        if self.max_day_usd.is_some() {
+            options.max_day_usd = self.max_day_usd;
+        }
+        if let Some(on_budget) = self.on_budget {
+            options.on_exceed = on_budget;
+        }
+        if self.degrade_model.is_some() {
+            options.degrade_model = self.degrade_model.clone();
+        }
+    }
+}
+
+// Per-phase sampling parameters. Overrides the `[generation.<phase>]` config sections.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Generation options")]
+pub struct GenerationArgs {
+    /// Set a generation parameter for one phase (keyword, answer, follow-up, critique, agent, structured), e.g.
+    /// keyword.temperature=0.2 or answer.max_tokens=800. Repeatable.
+    #[arg(long = "gen-param", global = true, value_name = "PHASE.NAME=VALUE", value_parser = parse_setting)]
+    pub gen_params: Vec<GenerationSetting>,
+
+    /// How much reasoning models may think before answering (same as answer.reasoning_effort).
+    #[arg(long, global = true, value_enum, value_name = "EFFORT")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Show the answer's thinking dimmed on stderr, hide it, or include it in the output as a
+    /// collapsible block.
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    pub thinking: Option<ThinkingDisplay>,
+}
+
+impl GenerationArgs {
+    pub fn apply(&self, options: &mut GenerationOptions) {
+        if let Some(effort) = self.reasoning_effort {
+            options.set(&GenerationSetting { phase: PromptName::Answer, name: "reasoning_effort".to_string(), value: effort.as_str().to_string() });
+        }
+        for setting in &self.gen_params {
+            options.set(setting);
+        }
+    }
+
+    pub fn apply_reasoning(&self, options: &mut ReasoningOptions) {
+        if let Some(thinking) = self.thinking {
+            options.display = thinking;
+        }
+    }
+}
+
+// OpenRouter provider routing. Overrides the `[provider]` config section and the profile's.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Provider options")]
+pub struct ProviderArgs {
+    /// Providers to try first, in order (e.g. anthropic,openai).
+    #[arg(long, global = true, value_name = "PROVIDER", value_delimiter = ',')]
+    pub provider_order: Vec<String>,
+
+    /// Only route requests to these providers.
+    #[arg(long, global = true, value_name = "PROVIDER", value_delimiter = ',')]
+    pub provider_only: Vec<String>,
+
+    /// Never route requests to these providers.
+    #[arg(long, global = true, value_name = "PROVIDER", value_delimiter = ',')]
+    pub provider_ignore: Vec<String>,
+
+    /// Don't fall back to providers outside --provider-order.
+    #[arg(long, global = true)]
+    pub no_provider_fallbacks: bool,
+
+    /// Whether providers that store or train on prompts may be used.
+    #[arg(long, global = true, value_enum, value_name = "POLICY")]
+    pub data_collection: Option<DataCollection>,
+
+    /// Only use zero-data-retention endpoints.
+    #[arg(long, global = true)]
+    pub zdr: bool,
+
+    /// Prefer the cheapest, fastest or lowest-latency provider.
+    #[arg(long, global = true, value_enum, value_name = "BY")]
+    pub provider_sort: Option<ProviderSort>,
+}
+
+impl ProviderArgs {
+    pub fn apply(&self, options: &mut ProviderPreferences) {
+        if !self.provider_order.is_empty() {
+            options.order = self.provider_order.clone();
+        }
+        if !self.provider_only.is_empty() {
+            options.only = self.provider_only.clone();
+        }
+        if !self.provider_ignore.is_empty() {
+            options.ignore = self.provider_ignore.clone();
+        }
+        if self.no_provider_fallbacks {
+            options.allow_fallbacks = Some(false);
+        }
+        if self.data_collection.is_some() {
+            options.data_collection = self.data_collection;
+        }
+        if self.zdr {
+            options.zdr = Some(true);
+        }
+        if self.provider_sort.is_some() {
+            options.sort = self.provider_sort;
+        }
+    }
+}
+
+// Tool-calling research mode. Overrides the `[agent]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Agent options")]
+pub struct AgentArgs {
+    /// Let the answer model run the searches itself with tools, instead of the keyword phase.
+    #[arg(long, global = true)]
+    pub agent: bool,
+
+    /// Most model requests the agent may make; the last one has to answer.
+    #[arg(long, global = true, value_name = "N")]
+    pub max_steps: Option<usize>,
+}
+
+impl AgentArgs {
+    pub fn apply(&self, options: &mut AgentOptions) {
+        if self.agent {
+            options.enabled = true;
+        }
+        if let Some(max_steps) = self.max_steps {
+            options.max_steps = max_steps;
+        }
+    }
+}
+
+// JSON answers matching a schema. Overrides the `[structured]` config section.
+#[derive(Args, Debug, Default)]
+#[command(next_help_heading = "Structured output options")]
+pub struct StructuredArgs {
+    /// Answer with a JSON document matching this JSON Schema file instead of prose.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub schema: Option<PathBuf>,
+
+    /// Times to ask the model to fix an answer that doesn't match the schema.
+    #[arg(long, global = true, value_name = "N")]
+    pub max_repairs: Option<u32>,
+}
+
+impl StructuredArgs {
+    pub fn apply(&self, options: &mut StructuredOptions) {
+        if self.schema.is_some() {
+            options.schema = self.schema.clone();
+        }
+        if let Some(max_repairs) = self.max_repairs {
+            options.max_repairs = max_repairs;
+        }
+    }
+}
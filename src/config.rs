@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::agent::AgentOptions;
+use crate::budget::BudgetOptions;
+use crate::constants::app::{APP_DIR_NAME, CONFIG_FILE_NAME};
+use crate::context::ContextOptions;
+use crate::corpus::CorpusOptions;
+use crate::cost::CostOptions;
+use crate::dedup::DedupOptions;
+use crate::embeddings::EmbeddingOptions;
+use crate::fetcher::FetchOptions;
+use crate::generation::GenerationOptions;
+use crate::kb::KbOptions;
+use crate::language::LanguageOptions;
+use crate::locale::LocaleOptions;
+use crate::models::ExaSearchOptions;
+use crate::persona::PersonaOptions;
+use crate::prompts::PromptOptions;
+use crate::provider::ProviderPreferences;
+use crate::reasoning::ReasoningOptions;
+use crate::rerank::RerankOptions;
+use crate::structured::StructuredOptions;
+
+// Optional TOML config file. Looked up (first match wins) at --config, ./llm-search.toml
+// and <config dir>/llm-search/llm-search.toml. CLI flags override values from the file.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Profile applied when --profile is not given.
+    pub profile: Option<String>,
+    // Let the keyword phase suggest a recency window and domains for the Exa search.
+    pub keyword_hints: bool,
+    // Restrict the search to recent pages when the question asks for "latest", "this week", etc.
+    pub auto_recency: bool,
+    pub exa: ExaSearchOptions,
+    pub context: ContextOptions,
+    pub fetch: FetchOptions,
+    pub corpus: CorpusOptions,
+    pub embeddings: EmbeddingOptions,
+    pub rerank: RerankOptions,
+    pub kb: KbOptions,
+    pub dedup: DedupOptions,
+    pub prompts: PromptOptions,
+    pub persona: PersonaOptions,
+    pub locale: LocaleOptions,
+    pub language: LanguageOptions,
+    pub cost: CostOptions,
+    pub budget: BudgetOptions,
+    pub generation: GenerationOptions,
+    pub provider: ProviderPreferences,
+    pub reasoning: ReasoningOptions,
+    pub agent: AgentOptions,
+    pub structured: StructuredOptions,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+// A named set of settings layered over the rest of the file, e.g. one per team or use case.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub persona: Option<String>,
+    pub system: Option<String>,
+    // Replaces the top-level [provider] section, e.g. to require zero data retention.
+    pub provider: Option<ProviderPreferences>,
+}
+
+impl Config {
+    fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let known = self.profiles.keys().map(String::as_str).collect::<Vec<_>>().join(", ");
+            return Err(anyhow::anyhow!("Unknown profile `{}`. Profiles in the config file: {}", name, if known.is_empty() { "none" } else { &known }));
+        };
+        if profile.persona.is_some() {
+            self.persona.name = profile.persona;
+        }
+        if profile.system.is_some() {
+            self.persona.system = profile.system;
+        }
+        if let Some(provider) = profile.provider {
+            self.provider = provider;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            keyword_hints: true,
+            auto_recency: true,
+            exa: ExaSearchOptions::default(),
+            context: ContextOptions::default(),
+            fetch: FetchOptions::default(),
+            corpus: CorpusOptions::default(),
+            embeddings: EmbeddingOptions::default(),
+            rerank: RerankOptions::default(),
+            kb: KbOptions::default(),
+            dedup: DedupOptions::default(),
+            prompts: PromptOptions::default(),
+            persona: PersonaOptions::default(),
+            locale: LocaleOptions::default(),
+            language: LanguageOptions::default(),
+            cost: CostOptions::default(),
+            budget: BudgetOptions::default(),
+            generation: GenerationOptions::default(),
+            provider: ProviderPreferences::default(),
+            reasoning: ReasoningOptions::default(),
+            agent: AgentOptions::default(),
+            structured: StructuredOptions::default(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn load(explicit_path: Option<&Path>, profile: Option<&str>) -> Result<Config> {
+    let path = match explicit_path {
+        Some(path) => Some(path.to_path_buf()),
+        None => default_paths().into_iter().find(|path| path.is_file()),
+    };
+    let Some(path) = path else {
+        if let Some(profile) = profile {
+            return Err(anyhow::anyhow!("Profile `{}` requested, but no config file was found", profile));
+        }
+        return Ok(Config::default());
+    };
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let mut config: Config = toml::from_str(&raw)
+        .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+    normalize_dates(&mut config.exa)
+        .with_context(|| format!("Invalid date in config file {}", path.display()))?;
+    if let Some(profile) = profile.map(str::to_string).or_else(|| config.profile.clone()) {
+        config.apply_profile(&profile)?;
+    }
+    Ok(config)
+}
+
+fn default_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(CONFIG_FILE_NAME)];
+    if let Some(config_dir) = dirs::config_dir() {
+        paths.push(config_dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME));
+    }
+    paths
+}
+
+fn normalize_dates(options: &mut ExaSearchOptions) -> Result<()> {
+    if let Some(date) = &mut options.start_published_date {
+        *date = parse_published_date(date)?;
+    }
+    if let Some(date) = &mut options.end_published_date {
+        *date = parse_end_published_date(date)?;
+    }
+    Ok(())
+}
+
+// Accepts `YYYY-MM-DD` or an RFC 3339 timestamp and returns the ISO 8601 form Exa expects.
+// A bare date starts at midnight UTC.
+pub fn parse_published_date(value: &str) -> Result<String> {
+    parse_date(value, NaiveTime::MIN)
+}
+
+// Like `parse_published_date`, but a bare date runs to the end of that day, so the end date
+// itself is included.
+pub fn parse_end_published_date(value: &str) -> Result<String> {
+    parse_date(value, NaiveTime::from_hms_milli_opt(23, 59, 59, 999).unwrap())
+}
+
+fn parse_date(value: &str, time_of_day: NaiveTime) -> Result<String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(format_published_date(date.and_time(time_of_day).and_utc()));
+    }
+    let timestamp = DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("`{}` is not a YYYY-MM-DD date or RFC 3339 timestamp", value))?;
+    Ok(format_published_date(timestamp.with_timezone(&Utc)))
+}
+
+pub fn format_published_date(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_start_date_is_midnight() {
+        assert_eq!(parse_published_date("2024-03-01").unwrap(), "2024-03-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn bare_end_date_includes_the_whole_day() {
+        assert_eq!(parse_end_published_date("2024-03-01").unwrap(), "2024-03-01T23:59:59.999Z");
+    }
+
+    #[test]
+    fn timestamps_are_kept_as_given() {
+        assert_eq!(parse_published_date("2024-03-01T12:30:00+02:00").unwrap(), "2024-03-01T10:30:00.000Z");
+        assert_eq!(parse_end_published_date("2024-03-01T12:30:00Z").unwrap(), "2024-03-01T12:30:00.000Z");
+    }
+
+    #[test]
+    fn config_end_date_includes_the_whole_day() {
+        let mut options = ExaSearchOptions {
+            start_published_date: Some("2024-03-01".to_string()),
+            end_published_date: Some("2024-03-31".to_string()),
+            ..Default::default()
+        };
+        normalize_dates(&mut options).unwrap();
+        assert_eq!(options.start_published_date.as_deref(), Some("2024-03-01T00:00:00.000Z"));
+        assert_eq!(options.end_published_date.as_deref(), Some("2024-03-31T23:59:59.999Z"));
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        assert!(parse_published_date("March 1st").is_err());
+        assert!(parse_end_published_date("2024-02-30").is_err());
+    }
+}
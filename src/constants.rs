@@ -1,5 +1,8 @@
 pub mod open_router {
     pub const API_URL: &str = "https://oneapi.krabs.wang/openrouter-api/api/v1/chat/completions";
+    pub const MODELS_URL: &str = "https://oneapi.krabs.wang/openrouter-api/api/v1/models";
+    pub const GENERATION_URL: &str = "https://oneapi.krabs.wang/openrouter-api/api/v1/generation";
+    pub const GENERATION_STATS_ATTEMPTS: u32 = 4; // the stats appear shortly after the stream ends
     pub const SEARCH_MODEL: &str = "deepseek/deepseek-chat-v3-0324:free";
     pub const SUMMARY_MODEL: &str = "google/gemini-2.5-pro-exp-03-25";
     pub const APPNAME: &str = "Yooooo";
@@ -9,5 +12,48 @@ pub mod open_router {
 pub mod exa {
     pub const SEARCH_API_URL: &str = "https://api.exa.ai/search";
     pub const CONTENTS_API_URL: &str = "https://api.exa.ai/contents";
+    pub const FIND_SIMILAR_API_URL: &str = "https://api.exa.ai/findSimilar";
     pub const REQUEST_COUNT: usize = 10;
+    pub const DEDUP_EXTRA_RESULTS: usize = 5; // requested on top of REQUEST_COUNT to backfill duplicates
+    pub const SNIPPET_CHAR_LIMIT: usize = 500;
+}
+
+pub mod app {
+    pub const APP_DIR_NAME: &str = "llm-search";
+    pub const CONFIG_FILE_NAME: &str = "llm-search.toml";
+    pub const CORPUS_INDEX_FILE_NAME: &str = "corpus-index.json";
+    pub const PRICING_CACHE_FILE_NAME: &str = "openrouter-models.json";
+    pub const SPEND_LEDGER_FILE_NAME: &str = "spend.json";
+}
+
+pub mod fetch {
+    pub const USER_AGENT: &str = concat!("llm-search/", env!("CARGO_PKG_VERSION"));
+    pub const ROBOTS_MAX_BYTES: usize = 512 * 1024; // Google's cap; anything past it is ignored
+    pub const ACCEPT: &str = "text/html,application/xhtml+xml,text/plain;q=0.8";
+    pub const MAX_REDIRECTS: usize = 10; // same as reqwest's default policy
+}
+
+pub mod corpus {
+    pub const INDEX_VERSION: u32 = 1; // bump when the on-disk format changes
+    pub const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+    pub const PASSAGE_TOKENS: usize = 256;
+}
+
+pub mod embeddings {
+    pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+    pub const DEFAULT_MODEL: &str = "text-embedding-3-small";
+    pub const DEFAULT_API_KEY_ENV: &str = "EMBEDDINGS_API_KEY";
+    pub const BATCH_SIZE: usize = 64;
+    pub const PASSAGE_TOKENS: usize = 512; // per result, when reranking
+}
+
+pub mod kb {
+    pub const DIR_NAME: &str = "kb";
+    pub const MAX_CHUNKS_PER_PAGE: usize = 24;
+    pub const PASSAGES_PER_RESULT: usize = 3;
+}
+
+pub mod prompts {
+    pub const DIR_NAME: &str = "prompts";
+    pub const FILE_EXTENSION: &str = "jinja";
 }
@@ -0,0 +1,187 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::exa_client::SearchSource;
+use crate::models::{ExaContentOptions, ExaHighlightsOptions, ExaSummaryOptions};
+
+// Builds the web-search section of the final prompt from the fetched sources,
+// choosing full text, highlights or summaries so the whole section fits a token budget.
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentMode {
+    // Full text when it fits the per-source budget, then highlights, then the summary.
+    Auto,
+    Text,
+    Highlights,
+    Summary,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextOptions {
+    pub mode: ContentMode,
+    pub token_budget: usize,
+    pub highlight_sentences: u32,
+    pub highlights_per_url: u32,
+    // Defaults to the user's query.
+    pub highlight_query: Option<String>,
+    pub summary_query: Option<String>,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            mode: ContentMode::Auto,
+            token_budget: 8000,
+            highlight_sentences: 3,
+            highlights_per_url: 3,
+            highlight_query: None,
+            summary_query: None,
+        }
+    }
+}
+
+impl ContextOptions {
+    // The /contents payload for this mode. Text is always requested as the last-resort fallback.
+    pub fn exa_contents(&self, user_query: &str) -> ExaContentOptions {
+        let wants_highlights = matches!(self.mode, ContentMode::Auto | ContentMode::Highlights);
+        let wants_summary = matches!(self.mode, ContentMode::Auto | ContentMode::Summary);
+        ExaContentOptions {
+            text: true,
+            highlights: wants_highlights.then(|| ExaHighlightsOptions {
+                query: self.highlight_query.clone().unwrap_or_else(|| user_query.to_string()),
+                num_sentences: self.highlight_sentences,
+                highlights_per_url: self.highlights_per_url,
+            }),
+            summary: wants_summary.then(|| ExaSummaryOptions {
+                query: self.summary_query.clone().unwrap_or_else(|| user_query.to_string()),
+            }),
+        }
+    }
+}
+
+// Rough token estimate: ~4 ASCII characters per token, one token per other character (e.g. CJK).
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other): (usize, usize) = text.chars().fold((0, 0), |(ascii, other), c| {
+        if c.is_ascii() { (ascii + 1, other) } else { (ascii, other + 1) }
+    });
+    ascii.div_ceil(4) + other
+}
+
+// Cuts `text` to roughly `max_tokens`, preferring to stop at a line or sentence end.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let mut used = 0;
+    let mut end = 0;
+    for (index, c) in text.char_indices() {
+        used += if c.is_ascii() { 1 } else { 4 };
+        if used > max_tokens * 4 {
+            break;
+        }
+        end = index + c.len_utf8();
+    }
+    let cut = &text[..end];
+    // Delimiters differ in width ('。' is 3 bytes), so step past the one that matched.
+    let boundary = cut.char_indices().rev()
+        .find(|(_, c)| matches!(c, '\n' | '.' | '。'))
+        .filter(|(i, _)| *i > end / 2)
+        .map_or(end, |(i, c)| i + c.len_utf8());
+    format!("{}…", &text[..boundary])
+}
+
+pub fn build_context(sources: &[SearchSource], options: &ContextOptions) -> String {
+    format!("Summary of relevant web search results:\n{}", render_sources(sources, options, 1))
+}
+
+// The sources within the token budget, numbered from `first_number` so results gathered over
+// several steps (in agent mode) keep distinct numbers.
+pub fn render_sources(sources: &[SearchSource], options: &ContextOptions, first_number: usize) -> String {
+    let mut context = String::new();
+    let mut remaining_budget = options.token_budget;
+
+    for (i, source) in sources.iter().enumerate() {
+        // Budget left over by short sources is shared among the ones after them.
+        let budget = remaining_budget / (sources.len() - i);
+        let (label, content) = select_content(source, options.mode, budget);
+        remaining_budget = remaining_budget.saturating_sub(estimate_tokens(&content));
+
+        context.push_str(&format!("\nResult {}:\nTitle: {}\nURL: {}\n", first_number + i, source.title, source.url));
+        if let Some(published_date) = &source.published_date {
+            context.push_str(&format!("Published: {}\n", published_date));
+        }
+        if let Some(fetched_at) = &source.fetched_at {
+            context.push_str(&format!("Fetched: {} (saved from an earlier search)\n", fetched_at));
+        }
+        if !source.alternate_urls.is_empty() {
+            context.push_str(&format!("Also published at: {}\n", source.alternate_urls.join(", ")));
+        }
+        if let Some(author) = source.author.as_deref().filter(|a| !a.is_empty()) {
+            context.push_str(&format!("Author: {}\n", author));
+        }
+        if content.is_empty() {
+            context.push_str("Content: (No text content available)\n");
+        } else {
+            context.push_str(&format!("{}:\n{}\n", label, content));
+        }
+    }
+    context
+}
+
+fn select_content(source: &SearchSource, mode: ContentMode, budget: usize) -> (&'static str, String) {
+    let text = source.text.as_deref().filter(|t| !t.is_empty());
+    let highlights = (!source.highlights.is_empty()).then(|| source.highlights.join("\n… "));
+    let summary = source.summary.as_deref().filter(|s| !s.is_empty());
+    let fits = |content: &str| estimate_tokens(content) <= budget;
+
+    let preferred = match mode {
+        ContentMode::Text => text.map(|t| ("Content", t.to_string())),
+        ContentMode::Highlights => highlights.clone().map(|h| ("Highlights", h)),
+        ContentMode::Summary => summary.map(|s| ("Summary", s.to_string())),
+        ContentMode::Auto => None,
+    };
+    let choice = preferred
+        .or_else(|| text.filter(|t| fits(t)).map(|t| ("Content", t.to_string())))
+        .or_else(|| highlights.clone().filter(|h| fits(h)).map(|h| ("Highlights", h)))
+        .or_else(|| summary.map(|s| ("Summary", s.to_string())))
+        .or_else(|| highlights.map(|h| ("Highlights", h)))
+        .or_else(|| text.map(|t| ("Content", t.to_string())));
+
+    match choice {
+        Some((label, content)) => (label, truncate_to_tokens(&content, budget)),
+        None => ("Content", String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_kept() {
+        assert_eq!(truncate_to_tokens("Short text.", 100), "Short text.");
+    }
+
+    #[test]
+    fn ascii_text_stops_at_a_sentence_end() {
+        let text = "First sentence here. Second sentence here. Third sentence that goes on and on.";
+        assert_eq!(truncate_to_tokens(text, 12), "First sentence here. Second sentence here.…");
+    }
+
+    #[test]
+    fn cjk_text_stops_after_the_full_stop() {
+        let text = "第一句话在这里。第二句话也在这里。第三句话会被截断掉的内容";
+        let truncated = truncate_to_tokens(text, 20);
+        assert_eq!(truncated, "第一句话在这里。第二句话也在这里。…");
+        assert!(estimate_tokens(&truncated) <= 20 + 1);
+    }
+
+    #[test]
+    fn cjk_text_without_delimiters_is_cut_on_a_char_boundary() {
+        let text = "没有任何标点的很长的中文文本".repeat(10);
+        let truncated = truncate_to_tokens(&text, 7);
+        assert_eq!(truncated, "没有任何标点的…");
+    }
+}
@@ -0,0 +1,515 @@
+use anyhow::{Context, Result, Ok};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::config::format_published_date;
+use crate::constants::app::{APP_DIR_NAME, CORPUS_INDEX_FILE_NAME};
+use crate::constants::corpus::{INDEX_VERSION, MAX_FILE_BYTES, PASSAGE_TOKENS};
+use crate::context::estimate_tokens;
+use crate::exa_client::{ContentOrigin, SearchSource};
+use crate::extract::extract_readable;
+use crate::messages::msg;
+use crate::output;
+use crate::utils::create_spinner;
+
+// A directory of Markdown, text and HTML files, split into passages and indexed into an on-disk
+// inverted index. Queries are ranked with BM25 and come back as search sources, so local
+// documents share the context and citation scheme of the web results.
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorpusOptions {
+    // Search the index (when one has been built) alongside the web.
+    pub enabled: bool,
+    // Directory indexed by `index build` when none is given.
+    pub path: Option<PathBuf>,
+    // Defaults to <data dir>/llm-search/corpus-index.json.
+    pub index_path: Option<PathBuf>,
+    pub max_results: usize,
+    pub passages_per_document: usize,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            index_path: None,
+            max_results: 3,
+            passages_per_document: 3,
+        }
+    }
+}
+
+impl CorpusOptions {
+    pub fn index_path(&self) -> Result<PathBuf> {
+        match &self.index_path {
+            Some(path) => Ok(path.clone()),
+            None => dirs::data_dir()
+                .map(|dir| dir.join(APP_DIR_NAME).join(CORPUS_INDEX_FILE_NAME))
+                .context("No data directory found; set `index_path` in the [corpus] config section"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CorpusIndex {
+    version: u32,
+    root: PathBuf,
+    built_at: String,
+    documents: Vec<IndexedDocument>,
+    passages: Vec<Passage>,
+    // term -> (passage, term frequency)
+    postings: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndexedDocument {
+    path: PathBuf, // relative to the root
+    title: String,
+    modified: u64, // seconds since the epoch
+    size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Passage {
+    document: u32,
+    text: String,
+    length: u32, // in terms
+}
+
+#[derive(Debug, Default)]
+pub struct IndexStats {
+    pub added: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub skipped: usize,
+    pub passages: usize,
+}
+
+impl CorpusIndex {
+    // Indexes every supported file under `root`. With a previous index, files whose size and
+    // modification time are unchanged reuse their passages instead of being read again.
+    pub fn build(root: &Path, previous: Option<&CorpusIndex>) -> Result<(Self, IndexStats)> {
+        let root = root.canonicalize().with_context(|| format!("Corpus directory {} not found", root.display()))?;
+        let mut files = Vec::new();
+        collect_files(&root, &mut files).with_context(|| format!("Failed to list {}", root.display()))?;
+        files.sort();
+
+        let mut previous_documents: HashMap<&Path, (u32, &IndexedDocument)> = previous
+            .map(|index| index.documents.iter().enumerate().map(|(i, doc)| (doc.path.as_path(), (i as u32, doc))).collect())
+            .unwrap_or_default();
+        let mut previous_passages: HashMap<u32, Vec<&Passage>> = HashMap::new();
+        for passage in previous.into_iter().flat_map(|index| &index.passages) {
+            previous_passages.entry(passage.document).or_default().push(passage);
+        }
+        let mut index = Self {
+            version: INDEX_VERSION,
+            root: root.clone(),
+            built_at: format_published_date(chrono::Utc::now()),
+            documents: Vec::new(),
+            passages: Vec::new(),
+            postings: BTreeMap::new(),
+        };
+        let mut stats = IndexStats::default();
+
+        let spinner = create_spinner(&msg!("corpus.indexing", count = files.len()).yellow().to_string());
+        for (i, file) in files.iter().enumerate() {
+            spinner.set_message(msg!("corpus.indexing_file", index = i + 1, total = files.len()).yellow().to_string());
+            let relative = file.strip_prefix(&root).unwrap_or(file).to_path_buf();
+            let metadata = fs::metadata(file).with_context(|| format!("Failed to read {}", file.display()))?;
+            let modified = metadata.modified().ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs());
+            let document_id = index.documents.len() as u32;
+
+            if let Some((old_id, old)) = previous_documents.remove(relative.as_path())
+                && old.modified == modified && old.size == metadata.len()
+            {
+                let old_passages = previous_passages.remove(&old_id).unwrap_or_default();
+                index.passages.extend(old_passages.into_iter().map(|p| Passage { document: document_id, ..p.clone() }));
+                index.documents.push(old.clone());
+                stats.unchanged += 1;
+                continue;
+            }
+
+            let Some((title, text)) = read_document(file, metadata.len()) else {
+                stats.skipped += 1;
+                continue;
+            };
+            if previous.is_some_and(|p| p.documents.iter().any(|d| d.path == relative)) {
+                stats.changed += 1;
+            } else {
+                stats.added += 1;
+            }
+            let title = title.unwrap_or_else(|| file_stem(&relative));
+            for passage in split_passages(&text) {
+                let length = tokenize(&passage).len() as u32;
+                index.passages.push(Passage { document: document_id, text: passage, length });
+            }
+            index.documents.push(IndexedDocument { path: relative, title, modified, size: metadata.len() });
+        }
+        stats.removed = previous_documents.len();
+        stats.passages = index.passages.len();
+        index.rebuild_postings();
+        spinner.finish_with_message(msg!("corpus.indexed", count = index.documents.len(), passages = stats.passages).green().to_string());
+        Ok((index, stats))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path).with_context(|| format!("Failed to read corpus index {}", path.display()))?;
+        let index: Self = serde_json::from_str(&raw).with_context(|| format!("Failed to parse corpus index {}", path.display()))?;
+        if index.version != INDEX_VERSION {
+            return Err(anyhow::anyhow!("Corpus index {} has an old format; run `index build` again", path.display()));
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        // Write next to the target and rename, so an interrupted save never leaves a broken index.
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(self)?).with_context(|| format!("Failed to write {}", temporary.display()))?;
+        fs::rename(&temporary, path).with_context(|| format!("Failed to write corpus index {}", path.display()))?;
+        Ok(())
+    }
+
+    // BM25 over passages; documents rank by their best passage and carry their top passages
+    // (in document order) as text.
+    pub fn search(&self, query: &str, max_results: usize, passages_per_document: usize) -> Vec<SearchSource> {
+        const K1: f64 = 1.2;
+        const B: f64 = 0.75;
+        if self.passages.is_empty() {
+            return Vec::new();
+        }
+        let passage_count = self.passages.len() as f64;
+        let average_length = self.passages.iter().map(|p| f64::from(p.length)).sum::<f64>() / passage_count;
+
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else { continue };
+            let frequency = postings.len() as f64;
+            let idf = (1.0 + (passage_count - frequency + 0.5) / (frequency + 0.5)).ln();
+            for &(passage, tf) in postings {
+                let tf = f64::from(tf);
+                let length = f64::from(self.passages[passage as usize].length);
+                let score = idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length / average_length));
+                *scores.entry(passage).or_default() += score;
+            }
+        }
+
+        let mut by_document: HashMap<u32, Vec<(u32, f64)>> = HashMap::new();
+        for (passage, score) in scores {
+            by_document.entry(self.passages[passage as usize].document).or_default().push((passage, score));
+        }
+        let mut ranked: Vec<_> = by_document.into_iter()
+            .map(|(document, passages)| {
+                let best = passages.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+                (document, best, passages)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranked.into_iter().take(max_results).map(|(document, best, mut passages)| {
+            passages.sort_by(|a, b| b.1.total_cmp(&a.1));
+            passages.truncate(passages_per_document.max(1));
+            passages.sort_by_key(|(passage, _)| *passage);
+            let text = passages.iter()
+                .map(|(passage, _)| self.passages[*passage as usize].text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n…\n");
+
+            let document = &self.documents[document as usize];
+            let path = self.root.join(&document.path);
+            let url = reqwest::Url::from_file_path(&path).map_or_else(|_| path.display().to_string(), String::from);
+            SearchSource {
+                title: document.title.clone(),
+                score: Some(best),
+                text: Some(text),
+                origin: ContentOrigin::Corpus,
+                ..SearchSource::from_url(&url)
+            }
+        }).collect()
+    }
+
+    fn rebuild_postings(&mut self) {
+        self.postings.clear();
+        for (i, passage) in self.passages.iter().enumerate() {
+            let mut frequencies: HashMap<String, u32> = HashMap::new();
+            for term in tokenize(&passage.text) {
+                *frequencies.entry(term).or_default() += 1;
+            }
+            for (term, tf) in frequencies {
+                self.postings.entry(term).or_default().push((i as u32, tf));
+            }
+        }
+    }
+}
+
+// Loads the index and searches it, reporting (but not failing on) a missing or unreadable index.
+pub fn search_corpus(options: &CorpusOptions, query: &str) -> Vec<SearchSource> {
+    let path = match options.index_path() {
+        Result::Ok(path) if path.is_file() => path,
+        _ => return Vec::new(),
+    };
+    let index = match CorpusIndex::load(&path) {
+        Result::Ok(index) => index,
+        Err(e) => {
+            output::warn(msg!("corpus.skipped", error = format!("{:#}", e)).yellow());
+            return Vec::new();
+        }
+    };
+    let sources = index.search(query, options.max_results, options.passages_per_document);
+    output::detail(msg!("corpus.matches", dir = index.root.display(), count = sources.len()).dimmed());
+    sources
+}
+
+const SUPPORTED_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "text", "html", "htm"];
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Hidden files and directories (.git, editor state...) are never documents.
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if file_type.is_file() && path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+    std::result::Result::Ok(())
+}
+
+// Returns the title (when the file declares one) and the plain text.
+fn read_document(path: &Path, size: u64) -> Option<(Option<String>, String)> {
+    if size > MAX_FILE_BYTES {
+        output::detail(msg!("corpus.too_large", path = path.display(), limit = MAX_FILE_BYTES).dimmed());
+        return None;
+    }
+    let raw = match fs::read(path) {
+        Result::Ok(raw) => String::from_utf8_lossy(&raw).into_owned(),
+        Err(e) => {
+            output::warn(msg!("corpus.file_skipped", path = path.display(), error = e).yellow());
+            return None;
+        }
+    };
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => {
+            let page = extract_readable(&raw);
+            Some((page.title, page.text))
+        }
+        "md" | "markdown" => {
+            let title = raw.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string());
+            Some((title, raw))
+        }
+        _ => Some((None, raw)),
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned())
+}
+
+// Groups lines into passages of roughly PASSAGE_TOKENS, breaking only between lines.
+pub fn split_passages(text: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    for line in text.lines().map(str::trim_end).filter(|line| !line.trim().is_empty()) {
+        if !current.is_empty() && estimate_tokens(&current) + estimate_tokens(line) > PASSAGE_TOKENS {
+            passages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        passages.push(current);
+    }
+    passages
+}
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "how", "in", "is", "it", "of",
+    "on", "or", "that", "the", "this", "to", "was", "what", "when", "where", "which", "who", "why",
+    "with",
+];
+
+// Lowercased alphanumeric words minus stop words; CJK text has no spaces, so each CJK
+// character is its own term.
+pub fn tokenize(text: &str) -> Vec<String> {
+    fn flush(word: &mut String, terms: &mut Vec<String>) {
+        if !word.is_empty() && !STOP_WORDS.contains(&word.as_str()) {
+            terms.push(word.clone());
+        }
+        word.clear();
+    }
+    let mut terms = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush(&mut word, &mut terms);
+            terms.push(c.to_string());
+        } else if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+        } else {
+            flush(&mut word, &mut terms);
+        }
+    }
+    flush(&mut word, &mut terms);
+    terms
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{ac00}'..='\u{d7af}' // Hangul
+        | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn sample_corpus(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        write(&dir.0, "rust.md", "# Rust\n\nRust is a systems programming language.\nOwnership and borrowing make memory safe without a garbage collector.\n");
+        write(&dir.0, "python.txt", "Python is a dynamic programming language.\nIt is popular for data science and scripting.\n");
+        write(&dir.0, "notes/cooking.html", "<html><head><title>Bread</title></head><body><p>Knead the dough, let it rise and bake the bread for forty minutes.</p></body></html>");
+        write(&dir.0, "notes/中文.md", "# 内存安全\n\n所有权和借用规则保证内存安全。\n");
+        // Not documents.
+        write(&dir.0, ".hidden/secret.md", "Ownership of the secret.");
+        write(&dir.0, "image.png", "Ownership");
+        dir
+    }
+
+    fn titles(sources: &[SearchSource]) -> Vec<&str> {
+        sources.iter().map(|source| source.title.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenization() {
+        let cases: [(&str, &[&str]); 7] = [
+            ("", &[]),
+            ("The quick, brown FOX!", &["quick", "brown", "fox"]),
+            ("Rust 1.80 is out", &["rust", "1", "80", "out"]),
+            ("École Ünïcode", &["école", "ünïcode"]),
+            ("中文分词", &["中", "文", "分", "词"]),
+            ("Rust语言，很快。", &["rust", "语", "言", "很", "快"]),
+            ("ひらがな 한국", &["ひ", "ら", "が", "な", "한", "국"]),
+        ];
+        for (text, terms) in cases {
+            assert_eq!(tokenize(text), terms, "{}", text);
+        }
+    }
+
+    #[test]
+    fn passage_splitting() {
+        assert_eq!(split_passages(""), Vec::<String>::new());
+        assert_eq!(split_passages("One\n\n  \nTwo  \n"), ["One\nTwo"]);
+
+        // 25 tokens per line: ten lines fit a 256-token passage, the eleventh starts the next.
+        let line = "x".repeat(100);
+        let text = vec![line.as_str(); 25].join("\n");
+        let passages = split_passages(&text);
+        assert_eq!(passages.iter().map(|p| p.lines().count()).collect::<Vec<_>>(), [10, 10, 5]);
+        assert!(passages.iter().all(|p| estimate_tokens(p) <= PASSAGE_TOKENS));
+
+        // A line longer than a passage is kept whole.
+        let long = "y".repeat(PASSAGE_TOKENS * 8);
+        assert_eq!(split_passages(&format!("short\n{}\nshort", long)), ["short", long.as_str(), "short"]);
+    }
+
+    #[test]
+    fn bm25_ranking() {
+        let dir = sample_corpus("corpus-ranking");
+        let (index, stats) = CorpusIndex::build(&dir.0, None).unwrap();
+        assert_eq!((stats.added, stats.changed, stats.unchanged, stats.removed), (4, 0, 0, 0));
+
+        assert_eq!(titles(&index.search("ownership and borrowing", 3, 1)), ["Rust"]);
+        assert_eq!(titles(&index.search("bread dough", 3, 1)), ["Bread"]);
+        assert_eq!(titles(&index.search("内存安全", 3, 1)), ["内存安全"]);
+        // Files without a title fall back to their name.
+        assert_eq!(titles(&index.search("data science", 3, 1)), ["python"]);
+        // A term in fewer passages weighs more: "systems" only appears in the Rust document.
+        assert_eq!(titles(&index.search("programming language systems", 3, 1)), ["Rust", "python"]);
+        assert_eq!(index.search("programming language", 1, 1).len(), 1);
+        assert!(index.search("the of and", 3, 1).is_empty());
+        assert!(index.search("quantum", 3, 1).is_empty());
+
+        let rust = &index.search("ownership", 3, 1)[0];
+        assert_eq!(rust.origin, ContentOrigin::Corpus);
+        assert!(rust.url.starts_with("file://") && rust.url.ends_with("rust.md"), "{}", rust.url);
+        assert!(rust.text.as_deref().is_some_and(|text| text.contains("garbage collector")));
+    }
+
+    #[test]
+    fn update_reuses_unchanged_files() {
+        let dir = sample_corpus("corpus-update");
+        let (previous, _) = CorpusIndex::build(&dir.0, None).unwrap();
+
+        // Sizes differ, so the change is seen even within the same second.
+        write(&dir.0, "python.txt", "Python is a dynamic programming language used for automation.\n");
+        fs::remove_file(dir.0.join("notes/cooking.html")).unwrap();
+        write(&dir.0, "go.md", "# Go\n\nGo has goroutines and channels.\n");
+
+        let (index, stats) = CorpusIndex::build(&dir.0, Some(&previous)).unwrap();
+        assert_eq!((stats.added, stats.changed, stats.unchanged, stats.removed, stats.skipped), (1, 1, 2, 1, 0));
+        assert_eq!(index.documents.len(), 4);
+
+        assert_eq!(titles(&index.search("goroutines", 3, 1)), ["Go"]);
+        assert_eq!(titles(&index.search("automation", 3, 1)), ["python"]);
+        assert!(index.search("data science", 3, 1).is_empty());
+        assert!(index.search("bread", 3, 1).is_empty());
+        // Reused passages point at the documents' new positions.
+        assert_eq!(titles(&index.search("ownership", 3, 1)), ["Rust"]);
+        assert_eq!(titles(&index.search("所有权", 3, 1)), ["内存安全"]);
+
+        let (_, stats) = CorpusIndex::build(&dir.0, Some(&index)).unwrap();
+        assert_eq!((stats.added, stats.changed, stats.unchanged, stats.removed), (0, 0, 4, 0));
+    }
+
+    #[test]
+    fn saved_index_loads_back() {
+        let dir = sample_corpus("corpus-save");
+        let (index, _) = CorpusIndex::build(&dir.0, None).unwrap();
+        let path = dir.0.join("index/corpus-index.json");
+        index.save(&path).unwrap();
+
+        let loaded = CorpusIndex::load(&path).unwrap();
+        assert_eq!(loaded.root(), index.root());
+        assert_eq!(titles(&loaded.search("ownership", 3, 1)), ["Rust"]);
+    }
+}
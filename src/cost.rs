@@ -0,0 +1,376 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::constants::app::{APP_DIR_NAME, PRICING_CACHE_FILE_NAME, SPEND_LEDGER_FILE_NAME};
+use crate::constants::open_router::MODELS_URL;
+use crate::messages::msg;
+use crate::models::{OpenRouterModelsResponse, UsageInfo};
+use crate::output;
+
+// Dollar cost of a run: OpenRouter calls priced from the models endpoint (cached, with a
+// built-in fallback table), plus the cost Exa reports with each response. Each run's totals
+// are added to a per-day spend ledger in the data directory.
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostOptions {
+    // Show the cost of each call and the run's total.
+    pub enabled: bool,
+    // Add each run's spend to the daily ledger.
+    pub ledger: bool,
+    // Refetch OpenRouter's prices when the cached copy is older than this.
+    pub pricing_ttl_hours: u64,
+    // Query OpenRouter's generation stats after each call (provider, latency, native token
+    // counts, exact cost). Costs a request and a short wait per call.
+    pub generation_stats: bool,
+    // Prices (USD per million tokens) that replace OpenRouter's, e.g. for a proxy's own rates.
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for CostOptions {
+    fn default() -> Self {
+        Self { enabled: true, ledger: true, pricing_ttl_hours: 24, generation_stats: false, prices: BTreeMap::new() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+// Used when OpenRouter's models endpoint can't be reached and nothing is cached, in USD per
+// million tokens. `:free` variants are always free and need no entry.
+const FALLBACK_PRICES: &[(&str, f64, f64)] = &[
+    ("openai/gpt-4o", 2.5, 10.0),
+    ("openai/gpt-4o-mini", 0.15, 0.6),
+    ("openai/gpt-4.1", 2.0, 8.0),
+    ("openai/gpt-4.1-mini", 0.4, 1.6),
+    ("anthropic/claude-3.5-sonnet", 3.0, 15.0),
+    ("anthropic/claude-3.7-sonnet", 3.0, 15.0),
+    ("anthropic/claude-sonnet-4", 3.0, 15.0),
+    ("google/gemini-2.5-pro", 1.25, 10.0),
+    ("google/gemini-2.5-flash", 0.3, 2.5),
+    ("google/gemini-2.0-flash-001", 0.1, 0.4),
+    ("deepseek/deepseek-chat-v3-0324", 0.27, 1.1),
+    ("deepseek/deepseek-r1", 0.55, 2.19),
+];
+
+// What the models endpoint says about a model besides its price, used to check generation
+// parameters. Unknown for models missing from the list or from an older cache.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ModelCapabilities {
+    pub context_length: Option<u64>,
+    pub max_completion_tokens: Option<u32>,
+    pub supported_parameters: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PricingCache {
+    fetched_at: i64,
+    prices: BTreeMap<String, ModelPrice>,
+    #[serde(default)]
+    capabilities: BTreeMap<String, ModelCapabilities>,
+}
+
+#[derive(Debug, Default)]
+pub struct Pricing {
+    prices: BTreeMap<String, ModelPrice>,
+    overrides: BTreeMap<String, ModelPrice>,
+    capabilities: BTreeMap<String, ModelCapabilities>,
+}
+
+impl Pricing {
+    // Cached prices when fresh, else OpenRouter's current ones (refreshing the cache), else
+    // stale cached prices, else the fallback table. Never fails.
+    pub async fn load(http_client: &Client, options: &CostOptions) -> Self {
+        let cache_path = cache_path();
+        let cached = cache_path.as_deref().and_then(|path| {
+            serde_json::from_slice::<PricingCache>(&fs::read(path).ok()?).ok()
+        });
+        let max_age = i64::try_from(options.pricing_ttl_hours.saturating_mul(3600)).unwrap_or(i64::MAX);
+        let fresh = cached.as_ref().is_some_and(|cache| chrono::Utc::now().timestamp() - cache.fetched_at < max_age);
+
+        let cache = match cached {
+            Some(cache) if fresh => cache,
+            cached => match fetch_models(http_client).await {
+                Result::Ok(cache) => {
+                    if let Some(path) = &cache_path && let Err(e) = write_json(path, &cache) {
+                        output::detail(msg!("cost.prices_cache_failed", error = format!("{:#}", e)).dimmed());
+                    }
+                    cache
+                }
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    let message = if cached.is_some() { msg!("cost.prices_cached", error = error) } else { msg!("cost.prices_built_in", error = error) };
+                    output::detail(message.dimmed());
+                    cached.unwrap_or_else(|| PricingCache { prices: fallback_prices(), ..Default::default() })
+                }
+            },
+        };
+        Self { prices: cache.prices, overrides: options.prices.clone(), capabilities: cache.capabilities }
+    }
+
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        if model.ends_with(":free") {
+            return Some(ModelPrice { prompt: 0.0, completion: 0.0 });
+        }
+        self.overrides.get(model).or_else(|| self.prices.get(model)).copied()
+    }
+
+    // Dollar cost of a call, or None when the model's price is unknown.
+    pub fn cost(&self, model: &str, prompt_tokens: u32, completion_tokens: u32) -> Option<f64> {
+        let price = self.price(model)?;
+        Some((f64::from(prompt_tokens) * price.prompt + f64::from(completion_tokens) * price.completion) / 1_000_000.0)
+    }
+}
+
+async fn fetch_models(http_client: &Client) -> Result<PricingCache> {
+    let response = http_client
+        .get(MODELS_URL)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<OpenRouterModelsResponse>()
+        .await
+        .context("Failed to parse the OpenRouter models response")?;
+    // Prices are USD per token as strings; "-1" marks variable pricing, which can't be estimated.
+    let per_million = |price: &str| price.parse::<f64>().ok().filter(|price| *price >= 0.0).map(|price| price * 1_000_000.0);
+    let mut cache = PricingCache { fetched_at: chrono::Utc::now().timestamp(), ..Default::default() };
+    for model in response.data {
+        if let (Some(prompt), Some(completion)) = (per_million(&model.pricing.prompt), per_million(&model.pricing.completion)) {
+            cache.prices.insert(model.id.clone(), ModelPrice { prompt, completion });
+        }
+        cache.capabilities.insert(model.id, ModelCapabilities {
+            context_length: model.context_length,
+            max_completion_tokens: model.top_provider.and_then(|provider| provider.max_completion_tokens),
+            supported_parameters: model.supported_parameters,
+        });
+    }
+    Ok(cache)
+}
+
+fn fallback_prices() -> BTreeMap<String, ModelPrice> {
+    FALLBACK_PRICES.iter()
+        .map(|(model, prompt, completion)| (model.to_string(), ModelPrice { prompt: *prompt, completion: *completion }))
+        .collect()
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_DIR_NAME).join(PRICING_CACHE_FILE_NAME))
+}
+
+// What the current run has spent so far.
+#[derive(Debug, Default, Clone)]
+pub struct RunCost {
+    pub openrouter_usd: f64,
+    pub exa_usd: f64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub calls: u32,
+    // OpenRouter calls whose model has no known price; their cost is missing from the total.
+    pub unpriced_calls: u32,
+}
+
+impl RunCost {
+    pub fn total_usd(&self) -> f64 {
+        self.openrouter_usd + self.exa_usd
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+static PRICING: OnceLock<Pricing> = OnceLock::new();
+static RUN: Mutex<RunCost> = Mutex::new(RunCost {
+    openrouter_usd: 0.0,
+    exa_usd: 0.0,
+    prompt_tokens: 0,
+    completion_tokens: 0,
+    calls: 0,
+    unpriced_calls: 0,
+});
+
+static GENERATION_STATS: OnceLock<bool> = OnceLock::new();
+
+pub async fn init(http_client: &Client, options: &CostOptions) {
+    if PRICING.get().is_none() {
+        let _ = PRICING.set(Pricing::load(http_client, options).await);
+    }
+    let _ = GENERATION_STATS.set(options.generation_stats);
+}
+
+// What OpenRouter's model list says about a model, when it was loaded.
+pub fn capabilities(model: &str) -> Option<&'static ModelCapabilities> {
+    PRICING.get()?.capabilities.get(model)
+}
+
+pub fn generation_stats_enabled() -> bool {
+    GENERATION_STATS.get().copied().unwrap_or(false)
+}
+
+// Adds an OpenRouter call to the run, filling in `usage.cost` when OpenRouter didn't report it.
+pub fn record_openrouter(model: &str, usage: &mut UsageInfo) {
+    let completion_tokens = usage.completion_tokens.unwrap_or(0);
+    if usage.cost.is_none() {
+        usage.cost = PRICING.get().and_then(|pricing| pricing.cost(model, usage.prompt_tokens, completion_tokens));
+    }
+    let mut run = RUN.lock().unwrap();
+    run.calls += 1;
+    run.prompt_tokens += u64::from(usage.prompt_tokens);
+    run.completion_tokens += u64::from(completion_tokens);
+    match usage.cost {
+        Some(cost) => run.openrouter_usd += cost,
+        None => run.unpriced_calls += 1,
+    }
+}
+
+pub fn record_exa(dollars: f64) {
+    RUN.lock().unwrap().exa_usd += dollars;
+}
+
+// Dollar cost of a call that hasn't been made yet, or None when the model's price is unknown.
+pub fn estimate(model: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+    let clamp = |tokens: u64| u32::try_from(tokens).unwrap_or(u32::MAX);
+    PRICING.get()?.cost(model, clamp(prompt_tokens), clamp(completion_tokens))
+}
+
+pub fn run_cost() -> RunCost {
+    RUN.lock().unwrap().clone()
+}
+
+// " · $0.0012" for a usage line, or nothing when the cost is unknown or hidden.
+pub fn usage_suffix(options: &CostOptions, usage: &UsageInfo) -> String {
+    match usage.cost {
+        Some(cost) if options.enabled => format!(" · {}", format_usd(cost)),
+        _ => String::new(),
+    }
+}
+
+pub fn format_usd(dollars: f64) -> String {
+    if dollars > 0.0 && dollars < 0.0001 { "<$0.0001".to_string() } else { format!("${:.4}", dollars) }
+}
+
+// Prints the run's totals and adds them to today's ledger entry. Does nothing for runs that
+// made no paid calls.
+pub fn finish_run(options: &CostOptions) {
+    let run = run_cost();
+    if run.calls == 0 && run.exa_usd == 0.0 {
+        return;
+    }
+    let today = if options.ledger { record_in_ledger(&run) } else { None };
+    if !options.enabled {
+        return;
+    }
+    let mut line = msg!(
+        "cost.run",
+        total = format_usd(run.total_usd()),
+        openrouter = format_usd(run.openrouter_usd),
+        tokens = run.total_tokens(),
+        exa = format_usd(run.exa_usd),
+    );
+    if run.unpriced_calls > 0 {
+        line.push_str(&msg!("cost.unpriced", count = run.unpriced_calls));
+    }
+    if let Some(today) = today {
+        line.push_str(&msg!("cost.today", total = format_usd(today.total_usd()), runs = today.runs));
+    }
+    output::status(format!("\n{}", line.cyan()));
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct DaySpend {
+    pub runs: u32,
+    pub openrouter_usd: f64,
+    pub exa_usd: f64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl DaySpend {
+    pub fn total_usd(&self) -> f64 {
+        self.openrouter_usd + self.exa_usd
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+// Spend per local date (YYYY-MM-DD).
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SpendLedger {
+    pub days: BTreeMap<String, DaySpend>,
+}
+
+impl SpendLedger {
+    pub fn path() -> Result<PathBuf> {
+        dirs::data_dir()
+            .map(|dir| dir.join(APP_DIR_NAME).join(SPEND_LEDGER_FILE_NAME))
+            .context("No data directory found for the spend ledger")
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match fs::read(&path) {
+            Result::Ok(raw) => serde_json::from_slice(&raw).with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn today(&self) -> DaySpend {
+        self.days.get(&today_key()).cloned().unwrap_or_default()
+    }
+}
+
+fn today_key() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+// Adds the run to today's entry; returns the updated entry. A broken ledger only costs the record.
+fn record_in_ledger(run: &RunCost) -> Option<DaySpend> {
+    let result = (|| -> Result<DaySpend> {
+        let mut ledger = SpendLedger::load()?;
+        let day = ledger.days.entry(today_key()).or_default();
+        day.runs += 1;
+        day.openrouter_usd += run.openrouter_usd;
+        day.exa_usd += run.exa_usd;
+        day.prompt_tokens += run.prompt_tokens;
+        day.completion_tokens += run.completion_tokens;
+        let today = day.clone();
+        write_json(&SpendLedger::path()?, &ledger)?;
+        Ok(today)
+    })();
+    match result {
+        Result::Ok(today) => Some(today),
+        Err(e) => {
+            output::warn(msg!("cost.ledger_write_failed", error = format!("{:#}", e)).yellow());
+            None
+        }
+    }
+}
+
+// Writes next to the target and renames, so an interrupted write never leaves a broken file.
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_vec_pretty(value)?).with_context(|| format!("Failed to write {}", temporary.display()))?;
+    fs::rename(&temporary, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::corpus::tokenize;
+use crate::utils::fnv1a;
+
+// Near-duplicate detection for search results: syndicated copies and mirrors of one article
+// are recognized by their canonical URL or by the MinHash of their text's word shingles.
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DedupOptions {
+    pub enabled: bool,
+    // Estimated Jaccard similarity of the texts' shingle sets above which two results are one.
+    pub similarity: f64,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self { enabled: true, similarity: 0.8 }
+    }
+}
+
+const SHINGLE_WORDS: usize = 5;
+const MINHASH_PERMUTATIONS: u64 = 64;
+// Shorter texts (teasers, empty crawls) say little about the article, so only URLs are compared.
+const MIN_TEXT_WORDS: usize = 40;
+
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "ref", "ref_src", "source", "cmpid", "igshid"];
+
+// Groups items (url, text) that are duplicates of each other. Returns one entry per group, in
+// order of first appearance: the index of the group's first item and the indices of the rest.
+pub fn group_duplicates(items: &[(&str, Option<&str>)], similarity: f64) -> Vec<(usize, Vec<usize>)> {
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut group_by_url: HashMap<String, usize> = HashMap::new();
+    let mut signatures: Vec<(usize, Vec<u64>)> = Vec::new(); // (group, signature)
+
+    for (i, (url, text)) in items.iter().enumerate() {
+        let canonical = canonical_url(url);
+        let signature = text.and_then(minhash);
+        let existing = group_by_url.get(&canonical).copied().or_else(|| {
+            let signature = signature.as_ref()?;
+            signatures.iter()
+                .find(|(_, other)| estimated_jaccard(signature, other) >= similarity)
+                .map(|(group, _)| *group)
+        });
+
+        let group = match existing {
+            Some(group) => {
+                groups[group].1.push(i);
+                group
+            }
+            None => {
+                groups.push((i, Vec::new()));
+                groups.len() - 1
+            }
+        };
+        group_by_url.entry(canonical).or_insert(group);
+        if let Some(signature) = signature {
+            signatures.push((group, signature));
+        }
+    }
+    groups
+}
+
+// The URL with the parts that don't change the document removed: scheme, `www.`/`m.`/`amp.`
+// host prefixes, tracking parameters, fragments, `/amp` and `index.html` suffixes, and
+// trailing slashes. Remaining query parameters are sorted.
+pub fn canonical_url(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return url.trim().to_lowercase();
+    };
+    let mut host = parsed.host_str().unwrap_or_default().to_lowercase();
+    for prefix in ["www.", "m.", "amp.", "mobile."] {
+        if let Some(stripped) = host.strip_prefix(prefix) {
+            host = stripped.to_string();
+        }
+    }
+
+    let mut path = parsed.path().to_string();
+    for suffix in ["/index.html", "/index.htm", "/index.php", "/amp", ".amp"] {
+        if let Some(stripped) = path.strip_suffix(suffix) {
+            path = stripped.to_string();
+        }
+    }
+    let path = path.trim_end_matches('/');
+
+    let mut params: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+    let query = params.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("&");
+
+    if query.is_empty() { format!("{}{}", host, path) } else { format!("{}{}?{}", host, path, query) }
+}
+
+// MinHash signature of the text's word shingles, or None when the text is too short to judge.
+fn minhash(text: &str) -> Option<Vec<u64>> {
+    let words = tokenize(text);
+    if words.len() < MIN_TEXT_WORDS {
+        return None;
+    }
+    let mut signature = vec![u64::MAX; MINHASH_PERMUTATIONS as usize];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let hash = fnv1a(shingle.join(" ").as_bytes());
+        for (seed, min) in (0..MINHASH_PERMUTATIONS).zip(signature.iter_mut()) {
+            *min = (*min).min(splitmix64(hash ^ seed.wrapping_mul(0x9e3779b97f4a7c15)));
+        }
+    }
+    Some(signature)
+}
+
+fn estimated_jaccard(a: &[u64], b: &[u64]) -> f64 {
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f64 / a.len() as f64
+}
+
+// A cheap, well-mixed 64-bit permutation; seeding it differently gives the MinHash family.
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "The city council voted on Tuesday to expand the bike lane network across the downtown area, \
+        adding twelve kilometres of protected lanes over the next two years. Supporters said the plan would make \
+        commuting safer for thousands of residents, while some shop owners worried about losing parking spaces \
+        in front of their stores during the construction period and afterwards.";
+    const OTHER_ARTICLE: &str = "Researchers at the university have published a study showing that regular exercise \
+        improves memory in older adults. Participants who walked for thirty minutes a day performed better on \
+        recall tests after six months than those who did stretching exercises only, and brain scans showed \
+        growth in the region linked to learning and memory formation over the same period.";
+
+    #[test]
+    fn canonical_urls() {
+        let cases = [
+            ("https://example.com/article", "example.com/article"),
+            ("http://www.example.com/article/", "example.com/article"),
+            ("https://m.example.com/article///", "example.com/article"),
+            ("https://amp.example.com/article/amp", "example.com/article"),
+            ("https://example.com/article.amp", "example.com/article"),
+            ("https://example.com/news/index.html", "example.com/news"),
+            ("https://example.com/", "example.com"),
+            ("https://EXAMPLE.com/Article", "example.com/Article"),
+            ("https://example.com/article#comments", "example.com/article"),
+            ("https://example.com/article?utm_source=x&utm_medium=y", "example.com/article"),
+            ("https://example.com/article?fbclid=abc&gclid=def&ref=home", "example.com/article"),
+            ("https://example.com/article?id=2&utm_campaign=z&a=1", "example.com/article?a=1&id=2"),
+            ("https://example.com/search?q=rust", "example.com/search?q=rust"),
+            ("not a url ", "not a url"),
+        ];
+        for (url, canonical) in cases {
+            assert_eq!(canonical_url(url), canonical, "{}", url);
+        }
+    }
+
+    #[test]
+    fn short_texts_have_no_signature() {
+        assert_eq!(minhash("Too short to judge."), None);
+        assert!(minhash(ARTICLE).is_some());
+    }
+
+    #[test]
+    fn minhash_similarity() {
+        let article = minhash(ARTICLE).unwrap();
+        assert_eq!(estimated_jaccard(&article, &article), 1.0);
+
+        let edited = ARTICLE.replace("Tuesday", "Wednesday");
+        let similarity = estimated_jaccard(&article, &minhash(&edited).unwrap());
+        assert!((0.6..1.0).contains(&similarity), "one word changed: {}", similarity);
+
+        let syndicated = format!("{} Copyright 2024 Example News.", ARTICLE);
+        let similarity = estimated_jaccard(&article, &minhash(&syndicated).unwrap());
+        assert!(similarity >= 0.8, "suffix added: {}", similarity);
+
+        let similarity = estimated_jaccard(&article, &minhash(OTHER_ARTICLE).unwrap());
+        assert!(similarity < 0.1, "different articles: {}", similarity);
+    }
+
+    #[test]
+    fn groups_by_url_and_text() {
+        let syndicated = format!("{} Copyright 2024 Example News.", ARTICLE);
+        let items = [
+            ("https://example.com/bikes", Some(ARTICLE)),
+            ("https://science.example.org/memory", Some(OTHER_ARTICLE)),
+            ("https://www.example.com/bikes/?utm_source=feed", None),
+            ("https://mirror.example.net/story", Some(syndicated.as_str())),
+            ("https://example.com/short", Some("A teaser.")),
+            ("https://other.example.net/short", Some("A teaser.")),
+        ];
+        assert_eq!(group_duplicates(&items, 0.8), vec![(0, vec![2, 3]), (1, vec![]), (4, vec![]), (5, vec![])]);
+    }
+}
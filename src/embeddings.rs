@@ -0,0 +1,78 @@
+use anyhow::{Context, Result, Ok};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+
+use crate::constants::embeddings::{BATCH_SIZE, DEFAULT_API_KEY_ENV, DEFAULT_BASE_URL, DEFAULT_MODEL};
+use crate::messages::msg;
+use crate::models::{EmbeddingRequest, EmbeddingResponse};
+use crate::output;
+
+// Client for an OpenAI-compatible `/embeddings` endpoint, which can be a local server.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingOptions {
+    pub base_url: String,
+    pub model: String,
+    // Environment variable holding the API key; local servers usually need none.
+    pub api_key_env: String,
+    pub dimensions: Option<u32>,
+}
+
+impl Default for EmbeddingOptions {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            api_key_env: DEFAULT_API_KEY_ENV.to_string(),
+            dimensions: None,
+        }
+    }
+}
+
+// One embedding per input, in input order.
+pub async fn embed(http_client: &Client, options: &EmbeddingOptions, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+    let url = format!("{}/embeddings", options.base_url.trim_end_matches('/'));
+    let api_key = env::var(&options.api_key_env).ok().filter(|key| !key.is_empty());
+    let mut embeddings = Vec::with_capacity(inputs.len());
+    let mut total_tokens = 0;
+
+    for batch in inputs.chunks(BATCH_SIZE) {
+        let payload = EmbeddingRequest { model: &options.model, input: batch, dimensions: options.dimensions };
+        let mut request = http_client
+            .post(&url)
+            .timeout(Duration::from_secs(120))
+            .json(&payload);
+        if let Some(api_key) = &api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to send embeddings request to {}", url))?
+            .error_for_status()
+            .context("Embeddings request failed")?
+            .json::<EmbeddingResponse>()
+            .await
+            .context("Failed to parse embeddings response")?;
+
+        if response.data.len() != batch.len() {
+            return Err(anyhow::anyhow!("Embeddings endpoint returned {} vectors for {} inputs", response.data.len(), batch.len()));
+        }
+        total_tokens += response.usage.map_or(0, |usage| usage.total_tokens);
+        let mut data = response.data;
+        data.sort_by_key(|item| item.index);
+        embeddings.extend(data.into_iter().map(|item| item.embedding));
+    }
+    output::detail(msg!("embeddings.done", count = inputs.len(), model = options.model, tokens = total_tokens).dimmed());
+    Ok(embeddings)
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 { 0.0 } else { dot / (norm_a * norm_b) }
+}
@@ -3,24 +3,133 @@ use anyhow::{Context, Result, Ok};
 use std::collections::HashMap;
 use std::time::Duration;
 use colored::Colorize; // Added for terminal styling
+use crate::config::format_published_date;
+use crate::budget;
+use crate::cost;
+use crate::dedup::{DedupOptions, group_duplicates};
 use crate::models::{
-    ExaSearchRequest, ExaSearchResponse, ExaContentsRequest, ExaContentsResponse,
+    ExaSearchRequest, ExaSearchResponse, ExaSearchResult, ExaContentsRequest, ExaContentsResponse,
+    ExaFindSimilarRequest, ExaContentOptions, ExaContentResult, ExaCostDollars, ExaSearchOptions, KeywordSuggestion,
 };
+use crate::messages::msg;
+use crate::output;
 use crate::utils::create_spinner;
-use crate::constants::exa::{SEARCH_API_URL, CONTENTS_API_URL, REQUEST_COUNT};
+use crate::constants::exa::{SEARCH_API_URL, CONTENTS_API_URL, FIND_SIMILAR_API_URL, REQUEST_COUNT, DEDUP_EXTRA_RESULTS, SNIPPET_CHAR_LIMIT};
 
-// Helper function to fetch search results from Exa API
+// One search result after content extraction, ready to be shown or put into a prompt.
+#[derive(Debug, Clone)]
+pub struct SearchSource {
+    pub title: String,
+    pub url: String,
+    pub published_date: Option<String>,
+    pub author: Option<String>,
+    pub score: Option<f64>,
+    pub similarity: Option<f32>, // to the query, when reranked or retrieved by embedding
+    pub fetched_at: Option<String>, // when the content was fetched, for knowledge base pages
+    pub alternate_urls: Vec<String>, // near-duplicate copies collapsed into this source
+    pub text: Option<String>, // blank lines removed
+    pub highlights: Vec<String>, // best-scoring first
+    pub summary: Option<String>,
+    pub origin: ContentOrigin,
+}
+
+// Where a source's content came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentOrigin {
+    Contents, // Exa /contents
+    Search,   // /contents failed or was skipped, so the search response's own fields were used
+    Local,    // downloaded by the local page fetcher
+    Corpus,   // a document from the local corpus index
+    Kb,       // a page saved in the knowledge base by an earlier run
+}
+
+impl SearchSource {
+    // A source known only by its URL, for pages given directly rather than found by a search.
+    pub fn from_url(url: &str) -> Self {
+        Self {
+            title: String::new(),
+            url: url.to_string(),
+            published_date: None,
+            author: None,
+            score: None,
+            similarity: None,
+            fetched_at: None,
+            alternate_urls: Vec::new(),
+            text: None,
+            highlights: Vec::new(),
+            summary: None,
+            origin: ContentOrigin::Search,
+        }
+    }
+
+    pub fn has_content(&self) -> bool {
+        self.text.as_deref().is_some_and(|t| !t.is_empty())
+            || !self.highlights.is_empty()
+            || self.summary.as_deref().is_some_and(|s| !s.is_empty())
+    }
+}
+
+// Helper function to fetch search results from Exa API. With several queries (keywords in
+// different languages) each is searched and their hits are interleaved by rank.
 pub async fn fetch_exa_search_results(
+    http_client: &Client,
+    exa_api_key: &str,
+    search_queries: &[String],
+    search_options: &ExaSearchOptions,
+    content_options: Option<&ExaContentOptions>,
+    dedup_options: &DedupOptions,
+) -> Result<Vec<SearchSource>> {
+    budget::check_spent("Exa search")?;
+    let quoted: Vec<String> = search_queries.iter().map(|query| format!("\"{}\"", query)).collect();
+    let exa_spinner = create_spinner(&msg!("exa.searching", queries = quoted.join(" | ")).yellow().to_string());
+    let contents = search_contents(content_options);
+    let responses = futures_util::future::try_join_all(search_queries.iter()
+        .map(|query| search_exa(http_client, exa_api_key, query, search_options, &contents, dedup_options))).await?;
+
+    let mut ranked: Vec<Vec<ExaSearchResult>> = responses.into_iter().map(|results| results.into_iter().rev().collect()).collect();
+    let mut results: Vec<ExaSearchResult> = Vec::new();
+    while ranked.iter().any(|results| !results.is_empty()) {
+        for result in ranked.iter_mut().filter_map(Vec::pop) {
+            if !results.iter().any(|r| r.url == result.url) {
+                results.push(result);
+            }
+        }
+    }
+    results.truncate(requested_results(dedup_options));
+
+    if results.is_empty() {
+        exa_spinner.finish_with_message(msg!("exa.no_results").red().to_string());
+        output::warn(msg!("exa.no_results_hint").yellow());
+        return Err(anyhow::anyhow!("Exa API returned no results."));
+    }
+    exa_spinner.finish_with_message(msg!("exa.search_done").green().to_string());
+
+    let (results, alternates) = collapse_duplicates(results, dedup_options);
+    // The results already carry the requested contents, so /contents isn't needed.
+    let sources = fetch_sources(http_client, exa_api_key, &results, None).await?;
+    Ok(attach_alternates(sources, alternates))
+}
+
+// The contents to request with a search: the given ones, or just the text when the local
+// fetcher is the primary source.
+fn search_contents(content_options: Option<&ExaContentOptions>) -> ExaContentOptions {
+    content_options.cloned().unwrap_or(ExaContentOptions { text: true, ..Default::default() })
+}
+
+async fn search_exa(
     http_client: &Client,
     exa_api_key: &str,
     search_keywords: &str,
-) -> Result<String> {
-    let exa_spinner = create_spinner(&format!("Searching with Exa: \"{}\"", search_keywords).yellow().to_string());
+    search_options: &ExaSearchOptions,
+    contents: &ExaContentOptions,
+    dedup_options: &DedupOptions,
+) -> Result<Vec<ExaSearchResult>> {
     let exa_request_payload = ExaSearchRequest {
         query: search_keywords,
-        num_results: REQUEST_COUNT,
+        num_results: requested_results(dedup_options),
         use_autoprompt: false,
-        text: true, // Request text content
+        contents,
+        options: search_options,
     };
 
     let timeout = Duration::new(300, 0);
@@ -38,31 +147,162 @@ pub async fn fetch_exa_search_results(
         .await
         .context("Failed to parse Exa API JSON response")?;
 
-    // eprintln!("Raw Exa Search Response (debug): {:?}", exa_search_response);
+    output::detail(msg!("exa.returned",
+        count = exa_search_response.results.len(),
+        request = exa_search_response.request_id.as_deref().unwrap_or("?"),
+        search_type = exa_search_response.resolved_search_type.as_deref().unwrap_or("?"),
+    ).dimmed());
+    report_cost("search", exa_search_response.cost_dollars.as_ref());
+    Ok(exa_search_response.results)
+}
+
+// Finds pages similar to `url` via Exa /findSimilar, with their contents like a search.
+pub async fn fetch_exa_similar_results(
+    http_client: &Client,
+    exa_api_key: &str,
+    url: &str,
+    search_options: &ExaSearchOptions,
+    content_options: Option<&ExaContentOptions>,
+    dedup_options: &DedupOptions,
+) -> Result<Vec<SearchSource>> {
+    budget::check_spent("Exa /findSimilar")?;
+    let exa_spinner = create_spinner(&msg!("exa.finding_similar", url = url).yellow().to_string());
+    // /findSimilar has no search type; everything else carries over.
+    let similar_options = ExaSearchOptions { search_type: None, ..search_options.clone() };
+    let contents = search_contents(content_options);
+    let exa_request_payload = ExaFindSimilarRequest {
+        url,
+        num_results: requested_results(dedup_options),
+        exclude_source_domain: true,
+        contents: &contents,
+        options: &similar_options,
+    };
 
-    if exa_search_response.results.is_empty() {
-        exa_spinner.finish_with_message("❌ Exa found no relevant results.".red().to_string());
-        println!("{}", "Try using more general keywords or check the Exa API.".yellow());
-        return Err(anyhow::anyhow!("Exa API returned no results.")); 
+    let timeout = Duration::new(300, 0);
+
+    let exa_similar_response = http_client
+        .post(FIND_SIMILAR_API_URL)
+        .timeout(timeout)
+        .header("x-api-key", exa_api_key)
+        .json(&exa_request_payload)
+        .send()
+        .await?
+        .error_for_status()
+        .context("Exa /findSimilar request failed")?
+        .json::<ExaSearchResponse>()
+        .await
+        .context("Failed to parse Exa /findSimilar JSON response")?;
+
+    output::detail(msg!("exa.returned_similar",
+        count = exa_similar_response.results.len(),
+        request = exa_similar_response.request_id.as_deref().unwrap_or("?"),
+    ).dimmed());
+    report_cost("findSimilar", exa_similar_response.cost_dollars.as_ref());
+
+    if exa_similar_response.results.is_empty() {
+        exa_spinner.finish_with_message(msg!("exa.no_similar").red().to_string());
+        return Err(anyhow::anyhow!("Exa /findSimilar returned no results."));
     }
-    exa_spinner.finish_with_message("✅ Exa search completed.".green().to_string());
+    exa_spinner.finish_with_message(msg!("exa.similar_done").green().to_string());
 
-    let mut search_results_summary = String::new();
-    // This title is now handled in main.rs, so we can remove it or keep it if it's meant to be part of the returned string.
-    // For now, let's assume it's part of the string to be returned and styled there if needed, or styled here directly.
-    search_results_summary.push_str(&format!("{}\n", "Summary of relevant web search results:".bold().underline()));
+    let (results, alternates) = collapse_duplicates(exa_similar_response.results, dedup_options);
+    let sources = fetch_sources(http_client, exa_api_key, &results, None).await?;
+    Ok(attach_alternates(sources, alternates))
+}
+
+// With deduplication on, a few extra results are requested to backfill collapsed duplicates.
+fn requested_results(dedup_options: &DedupOptions) -> usize {
+    if dedup_options.enabled { REQUEST_COUNT + DEDUP_EXTRA_RESULTS } else { REQUEST_COUNT }
+}
+
+// Collapses near-duplicate hits into their best-ranked copy and keeps the first REQUEST_COUNT
+// distinct results. Returns the alternate URLs of each kept result, keyed by its URL.
+fn collapse_duplicates(results: Vec<ExaSearchResult>, dedup_options: &DedupOptions) -> (Vec<ExaSearchResult>, HashMap<String, Vec<String>>) {
+    if !dedup_options.enabled {
+        return (results, HashMap::new());
+    }
+    let items: Vec<(&str, Option<&str>)> = results.iter().map(|r| (r.url.as_str(), r.text.as_deref())).collect();
+    let groups = group_duplicates(&items, dedup_options.similarity);
+    let duplicates = results.len() - groups.len();
+
+    let mut slots: Vec<Option<ExaSearchResult>> = results.into_iter().map(Some).collect();
+    let mut alternates = HashMap::new();
+    let mut kept = Vec::with_capacity(REQUEST_COUNT);
+    for (first, rest) in groups.into_iter().take(REQUEST_COUNT) {
+        let Some(result) = slots[first].take() else { continue };
+        let mut urls: Vec<String> = Vec::new();
+        for duplicate in rest.iter().filter_map(|&i| slots[i].take()) {
+            if duplicate.url != result.url && !urls.contains(&duplicate.url) {
+                urls.push(duplicate.url);
+            }
+        }
+        if !urls.is_empty() {
+            alternates.insert(result.url.clone(), urls);
+        }
+        kept.push(result);
+    }
+    if duplicates > 0 {
+        output::status(msg!("exa.collapsed", count = duplicates).cyan());
+    }
+    (kept, alternates)
+}
 
-    let ids_to_fetch: Vec<String> = exa_search_response.results.iter()
-        .filter_map(|r| r.id.clone())
+fn attach_alternates(mut sources: Vec<SearchSource>, mut alternates: HashMap<String, Vec<String>>) -> Vec<SearchSource> {
+    for source in &mut sources {
+        source.alternate_urls = alternates.remove(&source.url).unwrap_or_default();
+    }
+    sources
+}
+
+// Fetches /contents for pages given by URL, without searching first.
+pub async fn fetch_exa_url_contents(
+    http_client: &Client,
+    exa_api_key: &str,
+    urls: &[String],
+    content_options: &ExaContentOptions,
+) -> Result<Vec<SearchSource>> {
+    budget::check_spent("Exa /contents")?;
+    // Exa ids are the page URLs, so the URLs can stand in for search hits.
+    let results: Vec<ExaSearchResult> = urls.iter()
+        .map(|url| ExaSearchResult {
+            id: url.clone(),
+            url: url.clone(),
+            title: None,
+            score: None,
+            published_date: None,
+            author: None,
+            image: None,
+            favicon: None,
+            text: None,
+            highlights: Vec::new(),
+            highlight_scores: Vec::new(),
+            summary: None,
+        })
+        .collect();
+    fetch_sources(http_client, exa_api_key, &results, Some(content_options)).await
+}
+
+// Fetches /contents for the given hits and extracts the text, highlights and summary of each,
+// falling back to whatever the search response itself carried. Without content options
+// /contents is skipped: searches request their contents inline.
+async fn fetch_sources(
+    http_client: &Client,
+    exa_api_key: &str,
+    results: &[ExaSearchResult],
+    content_options: Option<&ExaContentOptions>,
+) -> Result<Vec<SearchSource>> {
+    let ids_to_fetch: Vec<String> = results.iter()
+        .map(|r| r.id.clone())
         .collect();
 
-    let mut contents_map: HashMap<String, String> = HashMap::new();
+    let mut contents_map: HashMap<String, ExaContentResult> = HashMap::new();
 
-    if !ids_to_fetch.is_empty() {
-        let exa_getting_data_notify_text = String::from("Fetching detailed content (via Exa /contents)...".yellow().to_string());
+    if let Some(content_options) = content_options.filter(|_| !ids_to_fetch.is_empty()) {
+        let exa_getting_data_notify_text = msg!("exa.contents_spinner").yellow().to_string();
         let content_spinner = create_spinner(&exa_getting_data_notify_text);
         let contents_request_payload = ExaContentsRequest {
             ids: ids_to_fetch.iter().map(AsRef::as_ref).collect(),
+            contents: content_options,
         };
 
         match http_client
@@ -78,71 +318,209 @@ pub async fn fetch_exa_search_results(
                     Result::Ok(resp) => {
                         match resp.json::<ExaContentsResponse>().await {
                             Result::Ok(contents_response) => {
+                                output::detail(msg!("exa.contents_request", request = contents_response.request_id.as_deref().unwrap_or("?")).dimmed());
+                                report_cost("contents", contents_response.cost_dollars.as_ref());
+                                for status in contents_response.statuses.iter().filter(|s| s.status != "success") {
+                                    let reason = status.error.as_ref()
+                                        .map(|e| format!("{} (HTTP {})", e.tag.as_deref().unwrap_or("unknown"), e.http_status_code.unwrap_or(0)))
+                                        .unwrap_or_else(|| status.status.clone());
+                                    output::detail(msg!("exa.contents_unavailable", id = status.id, reason = reason).dimmed());
+                                }
                                 for content_result in contents_response.results {
-                                    contents_map.insert(content_result.id, content_result.text);
+                                    contents_map.insert(content_result.id.clone(), content_result);
                                 }
-                                content_spinner.finish_with_message(format!("✅ Successfully fetched detailed content for {} results.", contents_map.len()).green().to_string());
+                                content_spinner.finish_with_message(msg!("exa.contents_done", count = contents_map.len()).green().to_string());
                             }
                             Err(e) => {
-                                content_spinner.finish_with_message("⚠️ Failed to parse Exa /contents response.".yellow().to_string());
-                                eprintln!("{}", format!("Failed to parse Exa /contents JSON response: {:?}", e).red());
+                                content_spinner.finish_with_message(msg!("exa.contents_parse_failed").yellow().to_string());
+                                output::error(msg!("exa.contents_parse_error", error = format!("{:?}", e)).red());
                             }
                         }
                     }
                     Err(e) => {
-                        content_spinner.finish_with_message("❌ Exa /contents API request failed (status).".red().to_string());
-                        eprintln!("{}", format!("Exa /contents API request failed (status): {:?}", e).red());
+                        content_spinner.finish_with_message(msg!("exa.contents_status_failed").red().to_string());
+                        output::error(msg!("exa.contents_status_error", error = format!("{:?}", e)).red());
                     }
                 }
             }
             Err(e) => {
-                content_spinner.finish_with_message("❌ Failed to send Exa /contents API request.".red().to_string());
-                eprintln!("{}", format!("Failed to send request to Exa /contents API: {:?}", e).red());
+                content_spinner.finish_with_message(msg!("exa.contents_send_failed").red().to_string());
+                output::error(msg!("exa.contents_send_error", error = format!("{:?}", e)).red());
             }
         }
     }
 
-    let summary_notify_text = String::from("Extracting main content from search results...".yellow().to_string());
+    let summary_notify_text = msg!("exa.extracting").yellow().to_string();
     let summary_spinner = create_spinner(&summary_notify_text);
-    for (i, result) in exa_search_response.results.iter().enumerate() {
-        summary_spinner.set_message(format!("Processing result {}/{}...", i + 1, exa_search_response.results.len()).yellow().to_string());
-        
-        let title_str = format!("{}: {}", "Title".dimmed(), result.title.cyan());
-        let url_str = format!("{}: {}", "URL".dimmed(), result.url.underline().blue());
+    let mut sources = Vec::with_capacity(results.len());
+    for (i, result) in results.iter().enumerate() {
+        summary_spinner.set_message(msg!("exa.extracting_result", index = i + 1, total = results.len()).yellow().to_string());
+
+        let source = match contents_map.remove(&result.id) {
+            Some(content) => SearchSource {
+                title: result.title.clone().or(content.title).unwrap_or_default(),
+                url: result.url.clone(),
+                published_date: result.published_date.clone().or(content.published_date),
+                author: result.author.clone().or(content.author),
+                score: result.score,
+                similarity: None,
+                fetched_at: None,
+                alternate_urls: Vec::new(),
+                text: content.text.as_deref().map(clean_text),
+                highlights: sort_highlights(content.highlights, &content.highlight_scores),
+                summary: content.summary,
+                origin: ContentOrigin::Contents,
+            },
+            // Fallback to the fields from the initial search if /contents failed or wasn't used for this ID
+            None => SearchSource {
+                title: result.title.clone().unwrap_or_default(),
+                url: result.url.clone(),
+                published_date: result.published_date.clone(),
+                author: result.author.clone(),
+                score: result.score,
+                similarity: None,
+                fetched_at: None,
+                alternate_urls: Vec::new(),
+                text: result.text.as_deref().map(clean_text),
+                highlights: sort_highlights(result.highlights.clone(), &result.highlight_scores),
+                summary: result.summary.clone(),
+                origin: ContentOrigin::Search,
+            },
+        };
+        sources.push(source);
+    }
+    summary_spinner.finish_with_message(msg!("exa.extracted").green().to_string());
+    Ok(sources)
+}
+
+// Feeds the keyword phase's hints into the search options. Explicit settings from the
+// config file or CLI always win; returns a description of each hint that was applied.
+pub fn apply_keyword_hints(options: &mut ExaSearchOptions, suggestion: &KeywordSuggestion) -> Vec<String> {
+    let mut applied = Vec::new();
+    if let Some(days) = suggestion.recency_days.filter(|days| *days > 0)
+        && options.start_published_date.is_none()
+    {
+        let since = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+        options.start_published_date = Some(format_published_date(since));
+        applied.push(msg!("search.hint_recency", days = days));
+    }
+    let domains: Vec<String> = suggestion.domains.iter()
+        .map(|domain| domain.trim().to_lowercase())
+        .filter(|domain| !domain.is_empty() && domain.contains('.'))
+        .take(3)
+        .collect();
+    if !domains.is_empty() && options.include_domains.is_empty() && options.exclude_domains.is_empty() {
+        applied.push(msg!("search.hint_domains", domains = domains.join(", ")));
+        options.include_domains = domains;
+    }
+    applied
+}
+
+fn report_cost(endpoint: &str, cost: Option<&ExaCostDollars>) {
+    let Some(cost) = cost else { return };
+    cost::record_exa(cost.total);
+    let (search, contents) = cost.break_down.iter().fold((0.0, 0.0), |(search, contents), item| {
+        (search + item.search.unwrap_or(0.0), contents + item.contents.unwrap_or(0.0))
+    });
+    output::detail(msg!("exa.cost",
+        endpoint = endpoint,
+        total = format!("{:.4}", cost.total),
+        search = format!("{:.4}", search),
+        contents = format!("{:.4}", contents),
+    ).dimmed());
+}
 
+// Drops blank lines.
+fn clean_text(text: &str) -> String {
+    text.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<&str>>().join("\n")
+}
+
+fn sort_highlights(highlights: Vec<String>, scores: &[f64]) -> Vec<String> {
+    if scores.len() != highlights.len() {
+        return highlights;
+    }
+    let mut scored: Vec<(f64, String)> = scores.iter().copied().zip(highlights).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, highlight)| highlight).collect()
+}
+
+// Renders the sources for the terminal, with a short snippet per result.
+// The prompt gets its own budgeted rendering from `context::build_context`.
+pub fn render_search_results(sources: &[SearchSource]) -> String {
+    let mut search_results_summary = String::new();
+    search_results_summary.push_str(&format!("{}\n", msg!("results.heading").bold().underline()));
+
+    for (i, source) in sources.iter().enumerate() {
         search_results_summary.push_str(&format!(
-            "\n{}: {}\n{}
-{}
-",
-            "🔍 Result".bold(), (i + 1).to_string().bold(),
-            title_str,
-            url_str
+            "\n{}: {}\n{}: {}\n{}: {}\n",
+            msg!("results.result").bold(), (i + 1).to_string().bold(),
+            msg!("results.title").dimmed(), source.title.cyan(),
+            msg!("results.url").dimmed(), source.url.underline().blue(),
         ));
+        if let Some(published_date) = &source.published_date {
+            search_results_summary.push_str(&format!("{}: {}\n", msg!("results.published").dimmed(), published_date));
+        }
+        if let Some(fetched_at) = &source.fetched_at {
+            search_results_summary.push_str(&format!("{}: {}\n", msg!("results.fetched").dimmed(), fetched_at));
+        }
+        if !source.alternate_urls.is_empty() {
+            search_results_summary.push_str(&format!("{}: {}\n", msg!("results.also_at").dimmed(), source.alternate_urls.join(", ")));
+        }
+        if let Some(score) = source.score.filter(|_| output::is_verbose()) {
+            search_results_summary.push_str(&format!("{}: {:.3}\n", msg!("results.score").dimmed(), score));
+        }
+        if let Some(similarity) = source.similarity.filter(|_| output::is_verbose()) {
+            search_results_summary.push_str(&format!("{}: {:.3}\n", msg!("results.similarity").dimmed(), similarity));
+        }
 
-        let mut summary_to_display = format!("{}: {}\n", "Summary".dimmed(), "(No text content available)".italic());
-        if let Some(id) = &result.id {
-            if let Some(full_text) = contents_map.get(id) {
-                let cleaned_text: String = full_text.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<&str>>().join("\n");
-                let snippet = cleaned_text.chars().take(500).collect::<String>(); // Keep snippet length reasonable
-                if snippet.is_empty(){
-                    summary_to_display = format!("{}: {}\n", "Summary".dimmed(), "(Content is empty or not fetched after cleaning)".italic());
-                } else {
-                    summary_to_display = format!("{}:\n{}...\n", "Summary".dimmed(), snippet);
-                }
-            } else if result.text.is_some() && !result.text.as_ref().unwrap().is_empty() {
-                // Fallback to text field from initial search if /contents failed or wasn't used for this ID
-                let text_content = result.text.as_ref().unwrap();
-                let cleaned_text: String = text_content.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<&str>>().join("\n");
-                let snippet = cleaned_text.chars().take(500).collect::<String>();
-                if snippet.is_empty(){
-                    summary_to_display = format!("{}: {}\n", "Summary (from initial search)".dimmed(), "(Content is empty or not fetched after cleaning)".italic());
-                } else {
-                    summary_to_display = format!("{}:\n{}...\n", "Summary (from initial search)".dimmed(), snippet);
-                }
+        let label = match source.origin {
+            ContentOrigin::Contents => msg!("results.summary"),
+            ContentOrigin::Search => msg!("results.summary_search"),
+            ContentOrigin::Local => msg!("results.summary_direct"),
+            ContentOrigin::Corpus => msg!("results.summary_corpus"),
+            ContentOrigin::Kb => msg!("results.summary_kb"),
+        };
+        let content = source.summary.clone()
+            .or_else(|| (!source.highlights.is_empty()).then(|| source.highlights.join(" … ")))
+            .or_else(|| source.text.clone());
+        let summary_to_display = match content {
+            None => format!("{}: {}\n", msg!("results.summary").dimmed(), msg!("results.no_content").italic()),
+            Some(content) if content.is_empty() => format!("{}: {}\n", label.dimmed(), msg!("results.empty").italic()),
+            Some(content) => {
+                let snippet = content.chars().take(SNIPPET_CHAR_LIMIT).collect::<String>(); // Keep snippet length reasonable
+                format!("{}:\n{}...\n", label.dimmed(), snippet)
             }
-        }
+        };
         search_results_summary.push_str(&summary_to_display);
     }
-    summary_spinner.finish_with_message("✅ Main content extracted from search results.".green().to_string());
-    Ok(search_results_summary)
+    search_results_summary
+}
+
+// Renders the sources as a Markdown list; this is the stdout output when there is no question to answer.
+pub fn render_sources_markdown(sources: &[SearchSource]) -> String {
+    let mut markdown = String::new();
+    for (i, source) in sources.iter().enumerate() {
+        let title = if source.title.is_empty() { source.url.as_str() } else { source.title.as_str() };
+        markdown.push_str(&format!("{}. [{}]({})", i + 1, title.replace(['[', ']'], ""), source.url));
+        if let Some(published_date) = &source.published_date {
+            markdown.push_str(&format!(" — {}", published_date.get(..10).unwrap_or(published_date)));
+        }
+        if let Some(fetched_at) = &source.fetched_at {
+            markdown.push_str(&format!(" ({})", msg!("results.fetched_on", date = fetched_at.get(..10).unwrap_or(fetched_at))));
+        }
+        if let Some(similarity) = source.similarity.filter(|_| output::is_verbose()) {
+            markdown.push_str(&format!(" — {} {:.3}", msg!("results.similarity_inline"), similarity));
+        }
+        markdown.push('\n');
+        let blurb = source.summary.clone()
+            .or_else(|| source.highlights.first().cloned())
+            .or_else(|| source.text.as_ref().map(|t| t.chars().take(SNIPPET_CHAR_LIMIT / 2).collect()));
+        if let Some(blurb) = blurb.filter(|b| !b.trim().is_empty()) {
+            markdown.push_str(&format!("   {}\n", blurb.split_whitespace().collect::<Vec<_>>().join(" ")));
+        }
+        if !source.alternate_urls.is_empty() {
+            markdown.push_str(&format!("   {}: {}\n", msg!("results.also_at"), source.alternate_urls.join(", ")));
+        }
+    }
+    markdown
 }
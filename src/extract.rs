@@ -0,0 +1,333 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+
+// Readability-style main-content extraction: drop page chrome (navigation, ads, comments...),
+// score the containers that hold paragraphs and render the best one as plain text.
+
+pub struct ExtractedPage {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+// Elements that never hold article text.
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "nav", "header", "footer", "aside", "form",
+    "iframe", "svg", "canvas", "button", "select", "input", "textarea", "dialog",
+];
+const SKIPPED_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary", "dialog", "menu"];
+// class / id words that mark page chrome.
+const BOILERPLATE_WORDS: &[&str] = &[
+    "ad", "ads", "advert", "advertisement", "banner", "breadcrumb", "breadcrumbs", "comment",
+    "comments", "consent", "cookie", "cookies", "disqus", "footer", "masthead", "menu", "modal",
+    "nav", "navbar", "newsletter", "popup", "promo", "related", "share", "sharing", "sidebar",
+    "social", "sponsored", "subscribe", "widget",
+];
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "section", "article", "main", "ul", "ol", "dl", "dt", "dd", "blockquote",
+    "table", "tr", "figure", "figcaption", "address", "hr", "details", "summary",
+];
+// Below this, the chosen container probably missed the content and the whole body is used.
+const MIN_CONTENT_CHARS: usize = 200;
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+pub fn extract_readable(html: &str) -> ExtractedPage {
+    let document = Html::parse_document(html);
+    let title = page_title(&document);
+
+    let body = first(&document, "body").unwrap_or_else(|| document.root_element());
+    let mut text = best_candidate(&document).map(render_text).unwrap_or_default();
+    if text.chars().count() < MIN_CONTENT_CHARS {
+        text = render_text(body);
+    }
+    ExtractedPage { title, text }
+}
+
+fn page_title(document: &Html) -> Option<String> {
+    let og_title = first(document, r#"meta[property="og:title"]"#)
+        .and_then(|meta| meta.value().attr("content").map(str::to_string));
+    og_title
+        .or_else(|| first(document, "title").map(|t| t.text().collect()))
+        .or_else(|| first(document, "h1").map(|h| h.text().collect()))
+        .map(|title| collapse_whitespace(&title))
+        .filter(|title| !title.is_empty())
+}
+
+// Scores every paragraph's parent (and, at half weight, grandparent) by the amount of prose it
+// holds, then discounts link-heavy containers. Returns the highest-scoring container.
+fn best_candidate(document: &Html) -> Option<ElementRef<'_>> {
+    let paragraphs = Selector::parse("p, pre").unwrap();
+    let mut scores = HashMap::new();
+
+    for paragraph in document.select(&paragraphs) {
+        if has_boilerplate_ancestor(paragraph) {
+            continue;
+        }
+        let text: String = paragraph.text().collect();
+        let length = text.trim().chars().count();
+        if length < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let commas = text.matches([',', '，', '、']).count();
+        let score = 1.0 + commas as f64 + (length / 100).min(3) as f64;
+
+        let parent = paragraph.parent().and_then(ElementRef::wrap);
+        let grandparent = parent.and_then(|p| p.parent()).and_then(ElementRef::wrap);
+        for (ancestor, weight) in [(parent, 1.0), (grandparent, 0.5)] {
+            if let Some(ancestor) = ancestor {
+                scores.entry(ancestor.id()).or_insert((ancestor, 0.0)).1 += score * weight;
+            }
+        }
+    }
+
+    scores.into_values()
+        .map(|(element, score)| (element, score * (1.0 - link_density(element))))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element)
+}
+
+fn link_density(element: ElementRef) -> f64 {
+    let total: usize = element.text().map(|t| t.trim().len()).sum();
+    if total == 0 {
+        return 1.0;
+    }
+    let links = Selector::parse("a").unwrap();
+    let linked: usize = element.select(&links).flat_map(|a| a.text()).map(|t| t.trim().len()).sum();
+    linked as f64 / total as f64
+}
+
+fn is_boilerplate(element: ElementRef) -> bool {
+    let value = element.value();
+    let name = value.name();
+    if SKIPPED_TAGS.contains(&name) {
+        return true;
+    }
+    if value.attr("hidden").is_some() || value.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    if value.attr("role").is_some_and(|role| SKIPPED_ROLES.contains(&role)) {
+        return true;
+    }
+    // The page and its declared main content are never chrome, whatever their classes say.
+    if matches!(name, "html" | "body" | "main" | "article") {
+        return false;
+    }
+    let words = value.attr("class").into_iter().chain(value.attr("id"))
+        .flat_map(|attr| attr.split(|c: char| !c.is_ascii_alphanumeric()))
+        .map(str::to_ascii_lowercase);
+    words.into_iter().any(|word| BOILERPLATE_WORDS.contains(&word.as_str()))
+}
+
+fn has_boilerplate_ancestor(element: ElementRef) -> bool {
+    std::iter::once(element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .any(is_boilerplate)
+}
+
+// Plain text with one line per block; headings and list items keep a Markdown marker.
+fn render_text(root: ElementRef) -> String {
+    let mut writer = TextWriter::default();
+    write_children(root, &mut writer);
+    writer.finish()
+}
+
+fn write_children(element: ElementRef, writer: &mut TextWriter) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => writer.push_text(text),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else { continue };
+                if is_boilerplate(child) {
+                    continue;
+                }
+                write_element(child, writer);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_element(element: ElementRef, writer: &mut TextWriter) {
+    match element.value().name() {
+        "br" => writer.end_line(),
+        "pre" => {
+            writer.end_line();
+            for line in element.text().collect::<String>().lines() {
+                writer.push_raw_line(line);
+            }
+        }
+        name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+            writer.end_line();
+            let level = usize::from(name.as_bytes()[1] - b'0');
+            writer.push_text(&format!("{} ", "#".repeat(level)));
+            write_children(element, writer);
+            writer.end_line();
+        }
+        "li" => {
+            writer.end_line();
+            writer.push_text("- ");
+            write_children(element, writer);
+            writer.end_line();
+        }
+        "td" | "th" => {
+            writer.push_text(" ");
+            write_children(element, writer);
+            writer.push_text(" ");
+        }
+        name if BLOCK_TAGS.contains(&name) => {
+            writer.end_line();
+            write_children(element, writer);
+            writer.end_line();
+        }
+        _ => write_children(element, writer),
+    }
+}
+
+#[derive(Default)]
+struct TextWriter {
+    output: String,
+    line: String,
+    pending_space: bool,
+}
+
+impl TextWriter {
+    // Collapses runs of whitespace like a browser does for normal text.
+    fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.pending_space = true;
+            } else {
+                if self.pending_space && !self.line.is_empty() {
+                    self.line.push(' ');
+                }
+                self.pending_space = false;
+                self.line.push(c);
+            }
+        }
+    }
+
+    fn push_raw_line(&mut self, line: &str) {
+        self.end_line();
+        if !line.trim().is_empty() {
+            self.output.push_str(line.trim_end());
+            self.output.push('\n');
+        }
+    }
+
+    fn end_line(&mut self) {
+        let line = self.line.trim();
+        // A line holding only a list or heading marker carries no text.
+        if !line.is_empty() && line.trim_start_matches(['#', '-']).trim() != "" {
+            self.output.push_str(line);
+            self.output.push('\n');
+        }
+        self.line.clear();
+        self.pending_space = false;
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        self.output.trim_end().to_string()
+    }
+}
+
+fn first<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    document.select(&Selector::parse(selector).unwrap()).next()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAGRAPH: &str = "Rust is a systems programming language, focused on safety, speed and concurrency, without a garbage collector.";
+
+    fn article_page(chrome: &str) -> String {
+        format!(
+            "<html><head><title>Page title</title></head><body>{chrome}\
+             <div class=\"content\"><h2>Why Rust</h2><p>{p}</p><p>{p}</p><ul><li>Memory safety</li><li>Zero-cost abstractions</li></ul></div>\
+             </body></html>",
+            chrome = chrome,
+            p = PARAGRAPH,
+        )
+    }
+
+    #[test]
+    fn titles() {
+        let cases = [
+            (r#"<html><head><meta property="og:title" content="OG title"><title>Title</title></head></html>"#, Some("OG title")),
+            ("<html><head><title>  Spaced \n title </title></head></html>", Some("Spaced title")),
+            ("<html><body><h1>Heading</h1></body></html>", Some("Heading")),
+            ("<html><head><title>  </title></head></html>", None),
+            ("<html><body><p>No title</p></body></html>", None),
+        ];
+        for (html, title) in cases {
+            assert_eq!(extract_readable(html).title.as_deref(), title, "{}", html);
+        }
+    }
+
+    #[test]
+    fn page_chrome_is_dropped() {
+        let cases = [
+            "<nav><a href=\"/\">Home</a> <a href=\"/about\">About</a></nav>",
+            "<header>Site header</header><footer>Site footer</footer>",
+            "<div class=\"sidebar\">Sidebar links</div>",
+            "<div id=\"cookie-banner\">We use cookies</div>",
+            "<div role=\"navigation\">Skip to content</div>",
+            "<div hidden>Hidden text</div><div aria-hidden=\"true\">Also hidden</div>",
+            "<script>var tracking = 1;</script><style>p { color: red }</style>",
+        ];
+        for chrome in cases {
+            let text = extract_readable(&article_page(chrome)).text;
+            assert!(text.contains(PARAGRAPH), "{}", chrome);
+            for word in ["Home", "header", "footer", "Sidebar", "cookies", "Skip", "Hidden", "tracking", "color"] {
+                assert!(!text.contains(word), "`{}` kept from {}", word, chrome);
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_render_one_per_line() {
+        let text = extract_readable(&article_page("")).text;
+        assert_eq!(text, format!("## Why Rust\n{p}\n{p}\n- Memory safety\n- Zero-cost abstractions", p = PARAGRAPH));
+    }
+
+    #[test]
+    fn preformatted_text_keeps_its_lines() {
+        let html = format!(
+            "<html><body><article><p>{p}</p><pre>fn main() {{\n    println!(\"hi\");\n}}</pre><p>{p}</p></article></body></html>",
+            p = PARAGRAPH,
+        );
+        let text = extract_readable(&html).text;
+        assert!(text.contains("fn main() {\n    println!(\"hi\");\n}"), "{}", text);
+    }
+
+    #[test]
+    fn prose_beats_link_lists() {
+        let links: String = (0..20).map(|i| format!("<p><a href=\"/{i}\">A long related article link number {i}, with commas, and more</a></p>", i = i)).collect();
+        let html = format!(
+            "<html><body><div id=\"links\">{links}</div><div id=\"story\"><p>{p}</p><p>{p}</p><p>{p}</p></div></body></html>",
+            links = links,
+            p = PARAGRAPH,
+        );
+        let text = extract_readable(&html).text;
+        assert!(text.starts_with(PARAGRAPH), "{}", text);
+        assert!(!text.contains("related article"), "{}", text);
+    }
+
+    #[test]
+    fn short_pages_fall_back_to_the_body() {
+        let html = "<html><body><div><span>Just a short note</span></div><table><tr><td>a</td><td>b</td></tr></table></body></html>";
+        assert_eq!(extract_readable(html).text, "Just a short note\na b");
+    }
+
+    #[test]
+    fn cjk_text_is_kept() {
+        let paragraph = "这是一段足够长的中文段落，用来测试正文提取，包含多个逗号、顿号，以及句号。";
+        let html = format!("<html><body><nav>首页</nav><article><p>{p}</p><p>{p}</p></article></body></html>", p = paragraph);
+        let text = extract_readable(&html).text;
+        assert_eq!(text, format!("{p}\n{p}", p = paragraph));
+    }
+}
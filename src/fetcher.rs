@@ -0,0 +1,484 @@
+use anyhow::{Context, Result, Ok};
+use clap::ValueEnum;
+use colored::Colorize;
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode, Url, header, redirect};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::constants::fetch::{ACCEPT, MAX_REDIRECTS, ROBOTS_MAX_BYTES, USER_AGENT};
+use crate::exa_client::{ContentOrigin, SearchSource};
+use crate::extract::extract_readable;
+use crate::messages::msg;
+use crate::output;
+use crate::utils::create_spinner;
+
+// Downloads pages directly (instead of through Exa /contents), honoring robots.txt and a size
+// cap, and reduces them to their readable text.
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchMode {
+    // Only use Exa content.
+    Off,
+    // Download the pages Exa returned no content for.
+    Fallback,
+    // Download every page and skip Exa /contents; Exa's search text is kept for pages that fail.
+    Primary,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchOptions {
+    pub mode: FetchMode,
+    // Larger pages are skipped when they declare their size, and cut off otherwise.
+    pub max_bytes: usize,
+    pub timeout_secs: u64,
+    pub concurrency: usize,
+    pub respect_robots: bool,
+    pub user_agent: String,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            mode: FetchMode::Fallback,
+            max_bytes: 2_000_000,
+            timeout_secs: 15,
+            concurrency: 4,
+            respect_robots: true,
+            user_agent: USER_AGENT.to_string(),
+        }
+    }
+}
+
+pub struct FetchedPage {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+pub struct PageFetcher<'a> {
+    // Used for robots.txt, whose redirects are simply followed.
+    http_client: &'a Client,
+    // Used for pages; it doesn't follow redirects so that every hop can be checked against robots.txt.
+    page_client: Client,
+    options: &'a FetchOptions,
+    // robots.txt rules per origin; `None` when the file could not be read and nothing may be fetched.
+    robots: Mutex<HashMap<String, Option<RobotsRules>>>,
+}
+
+impl<'a> PageFetcher<'a> {
+    pub fn new(http_client: &'a Client, options: &'a FetchOptions) -> Self {
+        let page_client = Client::builder()
+            .redirect(redirect::Policy::none())
+            .build()
+            .expect("Failed to build the page fetching client");
+        Self { http_client, page_client, options, robots: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn mode(&self) -> FetchMode {
+        self.options.mode
+    }
+
+    // Downloads the pages that need it for the current mode and fills in their text.
+    // Failures are reported and leave the source as it was.
+    pub async fn complete_sources(&self, sources: &mut [SearchSource]) {
+        let targets: Vec<(usize, String)> = sources.iter().enumerate()
+            .filter(|(_, source)| match self.options.mode {
+                FetchMode::Off => false,
+                FetchMode::Fallback => !source.has_content(),
+                FetchMode::Primary => true,
+            })
+            .map(|(i, source)| (i, source.url.clone()))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let fetch_spinner = create_spinner(&msg!("fetch.spinner", count = targets.len()).yellow().to_string());
+        let results: Vec<(usize, String, Result<FetchedPage>)> = futures_util::stream::iter(targets.iter())
+            .map(|(i, url)| async move { (*i, url.clone(), self.fetch_page(url).await) })
+            .buffer_unordered(self.options.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut fetched = 0;
+        for (i, url, result) in results {
+            match result {
+                Result::Ok(page) if !page.text.is_empty() => {
+                    let source = &mut sources[i];
+                    if source.title.is_empty() {
+                        source.title = page.title.unwrap_or_default();
+                    }
+                    source.text = Some(page.text);
+                    source.origin = ContentOrigin::Local;
+                    fetched += 1;
+                }
+                Result::Ok(_) => output::detail(msg!("fetch.no_text", url = url).dimmed()),
+                Err(e) => output::detail(msg!("fetch.failed", url = url, error = format!("{:#}", e)).dimmed()),
+            }
+        }
+        if fetched == targets.len() {
+            fetch_spinner.finish_with_message(msg!("fetch.done", count = fetched).green().to_string());
+        } else {
+            fetch_spinner.finish_with_message(msg!("fetch.partial", count = fetched, total = targets.len()).yellow().to_string());
+        }
+    }
+
+    pub async fn fetch_page(&self, url: &str) -> Result<FetchedPage> {
+        let url = Url::parse(url).with_context(|| format!("`{}` is not a valid URL", url))?;
+        let response = self.get_page(url).await?;
+
+        let content_type = response.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/html")
+            .to_ascii_lowercase();
+        let is_html = content_type.starts_with("text/html") || content_type.starts_with("application/xhtml");
+        if !is_html && !content_type.starts_with("text/plain") {
+            return Err(anyhow::anyhow!("Unsupported content type {}", content_type));
+        }
+        if let Some(length) = response.content_length().filter(|length| *length as usize > self.options.max_bytes) {
+            return Err(anyhow::anyhow!("Page is {} bytes, over the {} byte limit", length, self.options.max_bytes));
+        }
+
+        let body = read_limited(response, self.options.max_bytes).await?;
+        let body = decode(&body, &content_type);
+        if is_html {
+            let page = extract_readable(&body);
+            Ok(FetchedPage { title: page.title, text: page.text })
+        } else {
+            let text = body.lines().map(str::trim_end).filter(|line| !line.trim().is_empty()).collect::<Vec<_>>().join("\n");
+            Ok(FetchedPage { title: None, text })
+        }
+    }
+
+    // Follows redirects itself so that a page allowed by robots.txt can't lead to one that isn't.
+    async fn get_page(&self, mut url: Url) -> Result<reqwest::Response> {
+        for _ in 0..=MAX_REDIRECTS {
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(anyhow::anyhow!("Only http and https pages can be fetched"));
+            }
+            if self.options.respect_robots && !self.robots_allows(&url).await {
+                return Err(anyhow::anyhow!("{} is disallowed by robots.txt", url));
+            }
+
+            let response = self.page_client
+                .get(url.clone())
+                .timeout(Duration::from_secs(self.options.timeout_secs))
+                .header(header::USER_AGENT, &self.options.user_agent)
+                .header(header::ACCEPT, ACCEPT)
+                .send()
+                .await?;
+            if !response.status().is_redirection() {
+                return Ok(response.error_for_status()?);
+            }
+            let location = response.headers()
+                .get(header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .with_context(|| format!("{} redirected without a location", url))?;
+            url = url.join(location).with_context(|| format!("{} redirected to an invalid URL `{}`", url, location))?;
+        }
+        Err(anyhow::anyhow!("More than {} redirects", MAX_REDIRECTS))
+    }
+
+    async fn robots_allows(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let cached = self.robots.lock().unwrap().get(&origin).cloned();
+        let rules = match cached {
+            Some(rules) => rules,
+            None => {
+                let rules = self.fetch_robots(&origin).await;
+                self.robots.lock().unwrap().insert(origin, rules.clone());
+                rules
+            }
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        rules.is_some_and(|rules| rules.allows(&path))
+    }
+
+    // Follows RFC 9309: a missing robots.txt (4xx) allows everything, while a server error
+    // or an unreachable host disallows everything.
+    async fn fetch_robots(&self, origin: &str) -> Option<RobotsRules> {
+        let response = self.http_client
+            .get(format!("{}/robots.txt", origin))
+            .timeout(Duration::from_secs(self.options.timeout_secs))
+            .header(header::USER_AGENT, &self.options.user_agent)
+            .send()
+            .await;
+        let response = match response {
+            Result::Ok(response) => response,
+            Err(e) => {
+                output::detail(msg!("fetch.robots_failed", origin = origin, error = e).dimmed());
+                return None;
+            }
+        };
+        let status = response.status();
+        if status.is_success() {
+            let body = read_limited(response, ROBOTS_MAX_BYTES).await.ok()?;
+            Some(RobotsRules::parse(&String::from_utf8_lossy(&body), &self.options.user_agent))
+        } else if status.is_client_error() || status == StatusCode::NOT_MODIFIED {
+            Some(RobotsRules::default())
+        } else {
+            output::detail(msg!("fetch.robots_status", origin = origin, status = status).dimmed());
+            None
+        }
+    }
+}
+
+// Reads at most `limit` bytes of the body.
+async fn read_limited(response: reqwest::Response, limit: usize) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.context("Failed to read page body")?;
+        let room = limit - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() >= limit {
+            output::detail(msg!("fetch.truncated", limit = limit).dimmed());
+            break;
+        }
+    }
+    Ok(body)
+}
+
+// Decodes with the charset from the Content-Type header, defaulting to UTF-8.
+fn decode(body: &[u8], content_type: &str) -> String {
+    let encoding = content_type.split(';')
+        .filter_map(|param| param.trim().strip_prefix("charset="))
+        .find_map(|label| encoding_rs::Encoding::for_label(label.trim_matches('"').as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+// The Allow / Disallow rules of the robots.txt groups that apply to our user agent.
+#[derive(Debug, Clone, Default)]
+struct RobotsRules {
+    rules: Vec<RobotsRule>,
+}
+
+type RobotsRule = (bool, String); // (allow, path pattern)
+
+impl RobotsRules {
+    fn parse(body: &str, user_agent: &str) -> Self {
+        let product = user_agent.split('/').next().unwrap_or(user_agent).trim().to_ascii_lowercase();
+        // Groups are one or more user-agent lines followed by their rules.
+        let mut groups: Vec<(Vec<String>, Vec<RobotsRule>)> = Vec::new();
+        let mut in_rules = true;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules || groups.is_empty() {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_rules = false;
+                    }
+                    groups.last_mut().unwrap().0.push(value.to_ascii_lowercase());
+                }
+                directive @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty Disallow allows everything, so it adds no rule.
+                    if let Some(group) = groups.last_mut().filter(|_| !value.is_empty()) {
+                        group.1.push((directive == "allow", value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // RFC 9309 matches the product token itself, case-insensitively.
+        let matches_us = |agents: &[String]| agents.contains(&product);
+        // Groups naming us replace the `*` group rather than adding to it.
+        let named = groups.iter().any(|(agents, _)| matches_us(agents));
+        let rules = groups.into_iter()
+            .filter(|(agents, _)| if named { matches_us(agents) } else { agents.iter().any(|agent| agent == "*") })
+            .flat_map(|(_, rules)| rules)
+            .collect();
+        Self { rules }
+    }
+
+    // The longest matching pattern decides; Allow wins a tie, and no match means allowed.
+    fn allows(&self, path: &str) -> bool {
+        self.rules.iter()
+            .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+// Prefix match where `*` matches any run of characters and a trailing `$` anchors the end.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENT: &str = "llm-search/0.1.0";
+
+    #[test]
+    fn group_selection() {
+        let cases = [
+            // No groups at all.
+            ("", "/private", true),
+            // The `*` group applies when nothing names us.
+            ("User-agent: *\nDisallow: /private", "/private", false),
+            ("User-agent: *\nDisallow: /private", "/public", true),
+            // A group naming us replaces the `*` group.
+            ("User-agent: *\nDisallow: /\n\nUser-agent: llm-search\nDisallow: /private", "/public", true),
+            ("User-agent: *\nDisallow: /\n\nUser-agent: llm-search\nDisallow: /private", "/private", false),
+            // Product tokens match case-insensitively, but not by prefix.
+            ("User-agent: LLM-Search\nDisallow: /", "/page", false),
+            ("User-agent: llm\nDisallow: /\n\nUser-agent: *\nAllow: /", "/page", true),
+            // Several user-agent lines share the rules that follow them.
+            ("User-agent: other\nUser-agent: llm-search\nDisallow: /shared", "/shared", false),
+            // Groups naming us are combined.
+            ("User-agent: llm-search\nDisallow: /a\n\nUser-agent: other\nDisallow: /b\n\nUser-agent: llm-search\nDisallow: /c", "/c", false),
+            ("User-agent: llm-search\nDisallow: /a\n\nUser-agent: other\nDisallow: /b\n\nUser-agent: llm-search\nDisallow: /c", "/b", true),
+            // An empty Disallow allows everything.
+            ("User-agent: *\nDisallow:", "/anything", true),
+            // Rules before any user-agent line belong to no group.
+            ("Disallow: /\nUser-agent: *\nAllow: /", "/page", true),
+            // Comments, odd casing and whitespace.
+            ("# robots\nuser-AGENT : * # everyone\n  disallow:  /tmp  # scratch", "/tmp/file", false),
+        ];
+        for (body, path, allowed) in cases {
+            assert_eq!(RobotsRules::parse(body, AGENT).allows(path), allowed, "{:?} for {}", body, path);
+        }
+    }
+
+    #[test]
+    fn longest_match_decides() {
+        let cases = [
+            ("Disallow: /docs\nAllow: /docs/public", "/docs/public/page", true),
+            ("Disallow: /docs\nAllow: /docs/public", "/docs/private", false),
+            ("Allow: /docs\nDisallow: /docs/private", "/docs/private/page", false),
+            // Allow wins a tie.
+            ("Disallow: /page\nAllow: /page", "/page", true),
+            ("Allow: /page\nDisallow: /page", "/page", true),
+            // Wildcards count towards the length.
+            ("Disallow: /\nAllow: /*.html", "/index.html", true),
+            ("Allow: /\nDisallow: /*?session=", "/cart?session=1", false),
+        ];
+        for (rules, path, allowed) in cases {
+            let body = format!("User-agent: *\n{}", rules);
+            assert_eq!(RobotsRules::parse(&body, AGENT).allows(path), allowed, "{:?} for {}", rules, path);
+        }
+    }
+
+    #[test]
+    fn pattern_matching() {
+        let cases = [
+            ("/", "/", true),
+            ("/", "/anything", true),
+            ("/fish", "/fish", true),
+            ("/fish", "/fish.html", true),
+            ("/fish", "/Fish", false),
+            ("/fish", "/catfish", false),
+            ("/fish/", "/fish", false),
+            ("/fish*", "/fishheads", true),
+            ("/*.php", "/index.php", true),
+            ("/*.php", "/folder/filename.php?parameters", true),
+            ("/*.php", "/windows.PHP", false),
+            ("/*.php$", "/filename.php", true),
+            ("/*.php$", "/filename.php?parameters", false),
+            ("/*.php$", "/filename.php/", false),
+            ("/fish*.php", "/fish.php", true),
+            ("/fish*.php", "/fishheads/catfish.php?parameters", true),
+            ("/fish*.php", "/Fish.PHP", false),
+            ("/page$", "/page", true),
+            ("/page$", "/page/", false),
+            ("/a*b*$", "/a-anything", false),
+            ("/a*b*$", "/a-b-anything", true),
+            ("/*a*a$", "/xa", false),
+            ("/*a*a$", "/xaya", true),
+        ];
+        for (pattern, path, matches) in cases {
+            assert_eq!(robots_pattern_matches(pattern, path), matches, "{} against {}", pattern, path);
+        }
+    }
+
+    // Serves `routes` on a local port until the test ends and records the requested paths.
+    async fn serve(routes: &'static [(&'static str, &'static str)]) -> (String, std::sync::Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requested = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = requested.clone();
+        tokio::spawn(async move {
+            while let Result::Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Result::Ok(0) | Err(_) => break,
+                        Result::Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+                let response = routes.iter()
+                    .find(|(route, _)| *route == path)
+                    .map_or("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n", |(_, response)| response);
+                log.lock().unwrap().push(path);
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        (base, requested)
+    }
+
+    #[tokio::test]
+    async fn redirects_are_checked_against_robots_txt() {
+        const PAGE: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 4\r\nConnection: close\r\n\r\npage";
+        static ROUTES: [(&str, &str); 5] = [
+            ("/robots.txt", "HTTP/1.1 200 OK\r\nContent-Length: 33\r\nConnection: close\r\n\r\nUser-agent: *\nDisallow: /private\n"),
+            ("/to-private", "HTTP/1.1 302 Found\r\nLocation: /private/page\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+            ("/to-public", "HTTP/1.1 301 Moved Permanently\r\nLocation: public\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+            ("/public", PAGE),
+            ("/private/page", PAGE),
+        ];
+        let (base, requested) = serve(&ROUTES).await;
+        let http_client = Client::new();
+        let options = FetchOptions::default();
+        let fetcher = PageFetcher::new(&http_client, &options);
+
+        let page = fetcher.fetch_page(&format!("{}/to-public", base)).await.unwrap();
+        assert_eq!(page.text, "page");
+
+        let error = fetcher.fetch_page(&format!("{}/to-private", base)).await.err().unwrap();
+        assert!(format!("{:#}", error).contains("/private/page is disallowed by robots.txt"), "{:#}", error);
+        assert_eq!(*requested.lock().unwrap(), ["/robots.txt", "/to-public", "/public", "/to-private"]);
+
+        // Without robots.txt the redirect is followed as usual.
+        let options = FetchOptions { respect_robots: false, ..FetchOptions::default() };
+        let fetcher = PageFetcher::new(&http_client, &options);
+        assert_eq!(fetcher.fetch_page(&format!("{}/to-private", base)).await.unwrap().text, "page");
+    }
+}
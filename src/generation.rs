@@ -0,0 +1,221 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::cost;
+use crate::messages::msg;
+use crate::output;
+use crate::prompts::PromptName;
+use crate::reasoning::{self, ReasoningEffort, ReasoningParams, ThinkingDisplay};
+
+// Sampling parameters for each OpenRouter request, set per phase (one per prompt) in the config
+// file or with --gen-param. Unset parameters are left to the model's defaults.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerationParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    // For reasoning models, e.g. `reasoning = { effort = "high" }`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningParams>,
+}
+
+// Parameter names as OpenRouter spells them in requests and `supported_parameters`.
+const PARAMETER_NAMES: &[&str] = &["temperature", "top_p", "max_tokens", "stop", "seed", "presence_penalty", "frequency_penalty", "reasoning"];
+
+// Names accepted by --gen-param; the `reasoning` object is set through its two fields.
+const SETTING_NAMES: &[&str] = &[
+    "temperature", "top_p", "max_tokens", "stop", "seed", "presence_penalty", "frequency_penalty", "reasoning_effort", "reasoning_max_tokens",
+];
+
+// OpenAI-compatible APIs accept at most four stop sequences.
+const MAX_STOP_SEQUENCES: usize = 4;
+
+impl GenerationParams {
+    // Rejects values no model accepts.
+    pub fn validate(&self, phase: PromptName) -> Result<()> {
+        let check = |name: &str, value: Option<f32>, min: f32, max: f32| -> Result<()> {
+            match value {
+                Some(value) if !(min..=max).contains(&value) => Err(anyhow::anyhow!(
+                    "`{}` for the {} phase must be between {} and {}, got {}", name, phase.as_str(), min, max, value
+                )),
+                _ => Ok(()),
+            }
+        };
+        check("temperature", self.temperature, 0.0, 2.0)?;
+        check("top_p", self.top_p, 0.0, 1.0)?;
+        check("presence_penalty", self.presence_penalty, -2.0, 2.0)?;
+        check("frequency_penalty", self.frequency_penalty, -2.0, 2.0)?;
+        if self.max_tokens == Some(0) {
+            return Err(anyhow::anyhow!("`max_tokens` for the {} phase must be at least 1", phase.as_str()));
+        }
+        if self.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(anyhow::anyhow!("At most {} `stop` sequences are allowed for the {} phase, got {}", MAX_STOP_SEQUENCES, phase.as_str(), self.stop.len()));
+        }
+        if let Some(reasoning) = &self.reasoning {
+            if reasoning.effort.is_some() && reasoning.max_tokens.is_some() {
+                return Err(anyhow::anyhow!("Set either the reasoning effort or its max_tokens for the {} phase, not both", phase.as_str()));
+            }
+            if reasoning.max_tokens == Some(0) {
+                return Err(anyhow::anyhow!("Reasoning `max_tokens` for the {} phase must be at least 1", phase.as_str()));
+            }
+        }
+        Ok(())
+    }
+
+    // The parameters to send to `model`: ones it doesn't support are dropped and `max_tokens` is
+    // capped at its completion limit, with a warning each. Thinking that won't be shown is
+    // excluded from the response.
+    pub fn for_model(&self, model: &str) -> GenerationParams {
+        let mut params = self.clone();
+        if reasoning::display() == ThinkingDisplay::Hide && let Some(reasoning) = &mut params.reasoning {
+            reasoning.exclude = Some(true);
+        }
+        let Some(capabilities) = cost::capabilities(model) else { return params };
+        if !capabilities.supported_parameters.is_empty() {
+            for name in PARAMETER_NAMES {
+                if params.is_set(name) && !capabilities.supported_parameters.iter().any(|supported| supported == name) {
+                    output::warn(msg!("generation.unsupported", name = name, model = model).yellow());
+                    params.clear(name);
+                }
+            }
+        }
+        if let (Some(max_tokens), Some(limit)) = (params.max_tokens, capabilities.max_completion_tokens) && max_tokens > limit {
+            output::warn(msg!("generation.max_tokens_capped", max_tokens = max_tokens, model = model, limit = limit).yellow());
+            params.max_tokens = Some(limit);
+        }
+        params
+    }
+
+    fn is_set(&self, name: &str) -> bool {
+        match name {
+            "temperature" => self.temperature.is_some(),
+            "top_p" => self.top_p.is_some(),
+            "max_tokens" => self.max_tokens.is_some(),
+            "stop" => !self.stop.is_empty(),
+            "seed" => self.seed.is_some(),
+            "presence_penalty" => self.presence_penalty.is_some(),
+            "frequency_penalty" => self.frequency_penalty.is_some(),
+            "reasoning" => self.reasoning.is_some(),
+            _ => false,
+        }
+    }
+
+    fn clear(&mut self, name: &str) {
+        match name {
+            "temperature" => self.temperature = None,
+            "top_p" => self.top_p = None,
+            "max_tokens" => self.max_tokens = None,
+            "stop" => self.stop.clear(),
+            "seed" => self.seed = None,
+            "presence_penalty" => self.presence_penalty = None,
+            "frequency_penalty" => self.frequency_penalty = None,
+            "reasoning" => self.reasoning = None,
+            _ => {}
+        }
+    }
+
+    // Sets one parameter from its text form; `stop` takes `|`-separated sequences.
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let float = || value.parse::<f32>().map_err(|_| anyhow::anyhow!("`{}` expects a number, got `{}`", name, value));
+        let integer = || value.parse::<u64>().map_err(|_| anyhow::anyhow!("`{}` expects a whole number, got `{}`", name, value));
+        match name {
+            "temperature" => self.temperature = Some(float()?),
+            "top_p" => self.top_p = Some(float()?),
+            "max_tokens" => self.max_tokens = Some(u32::try_from(integer()?)?),
+            "stop" => self.stop = value.split('|').map(str::to_string).collect(),
+            "seed" => self.seed = Some(integer()?),
+            "presence_penalty" => self.presence_penalty = Some(float()?),
+            "frequency_penalty" => self.frequency_penalty = Some(float()?),
+            "reasoning_effort" => {
+                let effort = ReasoningEffort::from_str(value, true)
+                    .map_err(|_| anyhow::anyhow!("`reasoning_effort` expects low, medium or high, got `{}`", value))?;
+                self.reasoning.get_or_insert_default().effort = Some(effort);
+            }
+            "reasoning_max_tokens" => self.reasoning.get_or_insert_default().max_tokens = Some(u32::try_from(integer()?)?),
+            _ => return Err(anyhow::anyhow!("Unknown generation parameter `{}`. Known: {}", name, SETTING_NAMES.join(", "))),
+        }
+        Ok(())
+    }
+}
+
+// The `[generation.<phase>]` config sections, named like the prompts.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerationOptions {
+    pub keyword: GenerationParams,
+    pub answer: GenerationParams,
+    #[serde(rename = "follow-up")]
+    pub follow_up: GenerationParams,
+    pub critique: GenerationParams,
+    pub agent: GenerationParams,
+    pub structured: GenerationParams,
+}
+
+impl GenerationOptions {
+    pub fn phase(&self, phase: PromptName) -> &GenerationParams {
+        match phase {
+            PromptName::Keyword => &self.keyword,
+            PromptName::Answer => &self.answer,
+            PromptName::FollowUp => &self.follow_up,
+            PromptName::Critique => &self.critique,
+            PromptName::Agent => &self.agent,
+            PromptName::Structured => &self.structured,
+        }
+    }
+
+    fn phase_mut(&mut self, phase: PromptName) -> &mut GenerationParams {
+        match phase {
+            PromptName::Keyword => &mut self.keyword,
+            PromptName::Answer => &mut self.answer,
+            PromptName::FollowUp => &mut self.follow_up,
+            PromptName::Critique => &mut self.critique,
+            PromptName::Agent => &mut self.agent,
+            PromptName::Structured => &mut self.structured,
+        }
+    }
+
+    pub fn set(&mut self, setting: &GenerationSetting) {
+        // The value was checked when the flag was parsed.
+        let _ = self.phase_mut(setting.phase).set(&setting.name, &setting.value);
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for phase in PromptName::value_variants() {
+            self.phase(*phase).validate(*phase)?;
+        }
+        Ok(())
+    }
+}
+
+// One --gen-param value: `<phase>.<name>=<value>`, e.g. `answer.max_tokens=800`.
+#[derive(Debug, Clone)]
+pub struct GenerationSetting {
+    pub phase: PromptName,
+    pub name: String,
+    pub value: String,
+}
+
+pub fn parse_setting(raw: &str) -> Result<GenerationSetting, String> {
+    let (key, value) = raw.split_once('=').ok_or("expected <phase>.<name>=<value>, e.g. answer.max_tokens=800")?;
+    let (phase, name) = key.split_once('.').ok_or("expected <phase>.<name>=<value>, e.g. keyword.temperature=0.2")?;
+    let phase = PromptName::from_str(phase.trim(), true)
+        .map_err(|_| format!("unknown phase `{}` (keyword, answer, follow-up, critique, agent or structured)", phase))?;
+    let setting = GenerationSetting { phase, name: name.trim().to_string(), value: value.trim().to_string() };
+    GenerationParams::default().set(&setting.name, &setting.value).map_err(|e| e.to_string())?;
+    Ok(setting)
+}
@@ -0,0 +1,441 @@
+use anyhow::{Context, Result, Ok};
+use clap::ValueEnum;
+use colored::Colorize;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::format_published_date;
+use crate::constants::app::APP_DIR_NAME;
+use crate::constants::kb::{DIR_NAME, MAX_CHUNKS_PER_PAGE, PASSAGES_PER_RESULT};
+use crate::corpus::split_passages;
+use crate::embeddings::{EmbeddingOptions, cosine_similarity, embed};
+use crate::exa_client::{ContentOrigin, SearchSource};
+use crate::messages::msg;
+use crate::output;
+use crate::utils::{create_spinner, fnv1a};
+
+// Knowledge base of everything fetched: page text is chunked, embedded and appended to files
+// under the data directory, so later questions can be answered from earlier research.
+//
+// Layout: `meta.json` (embedding model and dimensions), `chunks.jsonl` (one chunk per line,
+// with its provenance) and `vectors.f32` (the chunk embeddings, little-endian, in line order).
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KbMode {
+    Off,
+    // Save fetched pages, but never retrieve from them.
+    Store,
+    // Save fetched pages and add matching stored pages to the web results.
+    Augment,
+    // Answer from stored pages alone when enough of them match; search the web otherwise.
+    Prefer,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct KbOptions {
+    pub mode: KbMode,
+    // Defaults to <data dir>/llm-search/kb.
+    pub path: Option<PathBuf>,
+    // Minimum cosine similarity between the question and a stored chunk.
+    pub threshold: f32,
+    pub max_results: usize,
+    // In `prefer` mode, how many matching pages are enough to skip the web search.
+    pub min_results: usize,
+}
+
+impl Default for KbOptions {
+    fn default() -> Self {
+        Self {
+            mode: KbMode::Off,
+            path: None,
+            threshold: 0.35,
+            max_results: 5,
+            min_results: 3,
+        }
+    }
+}
+
+impl KbOptions {
+    pub fn dir(&self) -> Result<PathBuf> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => dirs::data_dir()
+                .map(|dir| dir.join(APP_DIR_NAME).join(DIR_NAME))
+                .context("No data directory found; set `path` in the [kb] config section"),
+        }
+    }
+
+    pub fn retrieves(&self) -> bool {
+        matches!(self.mode, KbMode::Augment | KbMode::Prefer)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct KbMeta {
+    model: String,
+    dimensions: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct KbChunk {
+    url: String,
+    title: String,
+    published_date: Option<String>,
+    fetched_at: String,
+    content_hash: String, // of the whole page text, to skip storing an unchanged page again
+    text: String,
+}
+
+pub struct KnowledgeBase {
+    dir: PathBuf,
+    meta: Option<KbMeta>,
+    chunks: Vec<KbChunk>,
+    vectors: Vec<f32>,
+    // Bytes of chunks.jsonl holding `chunks`. Anything after them, and after their vectors in
+    // vectors.f32, is the torn tail of an interrupted write.
+    chunks_bytes: u64,
+}
+
+impl KnowledgeBase {
+    pub fn open(dir: &Path) -> Result<Self> {
+        let mut kb = Self { dir: dir.to_path_buf(), meta: None, chunks: Vec::new(), vectors: Vec::new(), chunks_bytes: 0 };
+        let meta_path = dir.join("meta.json");
+        if !meta_path.is_file() {
+            return Ok(kb);
+        }
+        let meta: KbMeta = serde_json::from_str(&fs::read_to_string(&meta_path)?)
+            .with_context(|| format!("Failed to parse {}", meta_path.display()))?;
+
+        let chunks_path = dir.join("chunks.jsonl");
+        let mut line_ends = Vec::new();
+        if chunks_path.is_file() {
+            let mut offset = 0;
+            for (i, line) in fs::read(&chunks_path)?.split_inclusive(|byte| *byte == b'\n').enumerate() {
+                // Only the last line can lack its newline: it was cut off mid-write.
+                if !line.ends_with(b"\n") {
+                    break;
+                }
+                let chunk = serde_json::from_slice(line)
+                    .with_context(|| format!("Failed to parse line {} of {}", i + 1, chunks_path.display()))?;
+                kb.chunks.push(chunk);
+                offset += line.len() as u64;
+                line_ends.push(offset);
+            }
+        }
+        let vectors_path = dir.join("vectors.f32");
+        if vectors_path.is_file() {
+            kb.vectors = fs::read(&vectors_path)?
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+        }
+        // An interrupted write can leave one file ahead of the other; the unmatched tail is
+        // ignored here and cut off the files before the next append.
+        let complete = kb.chunks.len().min(kb.vectors.len() / meta.dimensions.max(1));
+        kb.chunks.truncate(complete);
+        kb.vectors.truncate(complete * meta.dimensions);
+        kb.chunks_bytes = complete.checked_sub(1).map_or(0, |last| line_ends[last]);
+        kb.meta = Some(meta);
+        Ok(kb)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.url.as_str()).collect::<HashSet<_>>().len()
+    }
+
+    // Chunks, embeds and appends the text of each source; unchanged pages are skipped.
+    // Returns the number of pages stored.
+    pub async fn store(&mut self, http_client: &Client, embedding_options: &EmbeddingOptions, sources: &[SearchSource]) -> Result<usize> {
+        self.check_model(embedding_options)?;
+        let stored: HashSet<(&str, &str)> = self.chunks.iter().map(|c| (c.url.as_str(), c.content_hash.as_str())).collect();
+        let fetched_at = format_published_date(chrono::Utc::now());
+        let mut new_chunks = Vec::new();
+        let mut pages = 0;
+        for source in sources.iter().filter(|s| matches!(s.origin, ContentOrigin::Contents | ContentOrigin::Search | ContentOrigin::Local)) {
+            let Some(text) = source.text.as_deref().filter(|t| !t.trim().is_empty()) else { continue };
+            let content_hash = format!("{:016x}", fnv1a(text.as_bytes()));
+            if stored.contains(&(source.url.as_str(), content_hash.as_str())) {
+                continue;
+            }
+            pages += 1;
+            for passage in split_passages(text).into_iter().take(MAX_CHUNKS_PER_PAGE) {
+                new_chunks.push(KbChunk {
+                    url: source.url.clone(),
+                    title: source.title.clone(),
+                    published_date: source.published_date.clone(),
+                    fetched_at: fetched_at.clone(),
+                    content_hash: content_hash.clone(),
+                    text: passage,
+                });
+            }
+        }
+        if new_chunks.is_empty() {
+            return Ok(0);
+        }
+
+        let spinner = create_spinner(&msg!("kb.saving", count = pages).yellow().to_string());
+        let inputs: Vec<String> = new_chunks.iter().map(|chunk| format!("{}\n{}", chunk.title, chunk.text)).collect();
+        let embeddings = match embed(http_client, embedding_options, &inputs).await {
+            Result::Ok(embeddings) => embeddings,
+            Err(e) => {
+                spinner.finish_with_message(msg!("kb.save_failed").red().to_string());
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.append(&embedding_options.model, new_chunks, embeddings) {
+            spinner.finish_with_message(msg!("kb.save_failed").red().to_string());
+            return Err(e);
+        }
+        spinner.finish_with_message(msg!("kb.saved", count = pages).green().to_string());
+        Ok(pages)
+    }
+
+    // Appends chunks and their embeddings. Both files are first cut back to the pairs loaded,
+    // so the torn tail of an interrupted write never shifts later chunks onto the wrong vectors.
+    fn append(&mut self, model: &str, new_chunks: Vec<KbChunk>, embeddings: Vec<Vec<f32>>) -> Result<()> {
+        if embeddings.len() != new_chunks.len() {
+            return Err(anyhow::anyhow!("Got {} embeddings for {} chunks", embeddings.len(), new_chunks.len()));
+        }
+        let dimensions = embeddings.first().map_or(0, Vec::len);
+        if let Some(meta) = &self.meta
+            && meta.dimensions != dimensions
+        {
+            return Err(anyhow::anyhow!("Embeddings have {} dimensions but the knowledge base uses {}", dimensions, meta.dimensions));
+        }
+
+        fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        if self.meta.is_none() {
+            let meta = KbMeta { model: model.to_string(), dimensions };
+            fs::write(self.dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+            self.meta = Some(meta);
+        }
+        let mut vectors_file = open_at(&self.dir.join("vectors.f32"), self.vectors.len() as u64 * 4)?;
+        let bytes: Vec<u8> = embeddings.iter().flatten().flat_map(|value| value.to_le_bytes()).collect();
+        vectors_file.write_all(&bytes)?;
+        let mut chunks_file = open_at(&self.dir.join("chunks.jsonl"), self.chunks_bytes)?;
+        let mut lines = String::new();
+        for chunk in &new_chunks {
+            lines.push_str(&serde_json::to_string(chunk)?);
+            lines.push('\n');
+        }
+        chunks_file.write_all(lines.as_bytes())?;
+
+        self.chunks_bytes += lines.len() as u64;
+        self.vectors.extend(embeddings.into_iter().flatten());
+        self.chunks.extend(new_chunks);
+        Ok(())
+    }
+
+    // The stored pages most similar to the query (latest fetch of each URL only), each carrying
+    // its best-matching chunks and its fetch date.
+    pub async fn query(
+        &self,
+        http_client: &Client,
+        embedding_options: &EmbeddingOptions,
+        query: &str,
+        options: &KbOptions,
+    ) -> Result<Vec<SearchSource>> {
+        let Some(meta) = &self.meta else { return Ok(Vec::new()) };
+        self.check_model(embedding_options)?;
+        let query_embedding = embed(http_client, embedding_options, &[query.to_string()]).await?
+            .into_iter().next().unwrap_or_default();
+
+        let mut latest: HashMap<&str, &str> = HashMap::new();
+        for chunk in &self.chunks {
+            let fetched_at = latest.entry(chunk.url.as_str()).or_insert(chunk.fetched_at.as_str());
+            if chunk.fetched_at.as_str() > *fetched_at {
+                *fetched_at = chunk.fetched_at.as_str();
+            }
+        }
+        let mut by_url: HashMap<&str, Vec<(f32, &KbChunk)>> = HashMap::new();
+        for (chunk, vector) in self.chunks.iter().zip(self.vectors.chunks_exact(meta.dimensions)) {
+            if latest.get(chunk.url.as_str()) != Some(&chunk.fetched_at.as_str()) {
+                continue;
+            }
+            let similarity = cosine_similarity(&query_embedding, vector);
+            if similarity >= options.threshold {
+                by_url.entry(chunk.url.as_str()).or_default().push((similarity, chunk));
+            }
+        }
+
+        let mut pages: Vec<(f32, Vec<(f32, &KbChunk)>)> = by_url.into_values()
+            .map(|mut chunks| {
+                chunks.sort_by(|a, b| b.0.total_cmp(&a.0));
+                chunks.truncate(PASSAGES_PER_RESULT);
+                (chunks[0].0, chunks)
+            })
+            .collect();
+        pages.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(pages.into_iter().take(options.max_results).map(|(similarity, chunks)| {
+            let first = chunks[0].1;
+            SearchSource {
+                title: first.title.clone(),
+                published_date: first.published_date.clone(),
+                fetched_at: Some(first.fetched_at.clone()),
+                similarity: Some(similarity),
+                text: Some(chunks.iter().map(|(_, chunk)| chunk.text.as_str()).collect::<Vec<_>>().join("\n…\n")),
+                origin: ContentOrigin::Kb,
+                ..SearchSource::from_url(&first.url)
+            }
+        }).collect())
+    }
+
+    // Vectors from different models can't be compared, so the model is fixed per knowledge base.
+    fn check_model(&self, embedding_options: &EmbeddingOptions) -> Result<()> {
+        match &self.meta {
+            Some(meta) if meta.model != embedding_options.model => Err(anyhow::anyhow!(
+                "The knowledge base in {} was built with embedding model `{}`, not `{}`",
+                self.dir.display(), meta.model, embedding_options.model,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Opens a data file for writing at `len`, dropping whatever follows.
+fn open_at(path: &Path, len: u64) -> Result<File> {
+    let mut file = OpenOptions::new().create(true).write(true).truncate(false).open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.set_len(len)?;
+    file.seek(SeekFrom::End(0))?;
+    Ok(file)
+}
+
+// Retrieves from the knowledge base for the current mode, reporting (not failing on) errors.
+pub async fn retrieve(http_client: &Client, embedding_options: &EmbeddingOptions, options: &KbOptions, query: &str) -> Vec<SearchSource> {
+    if !options.retrieves() {
+        return Vec::new();
+    }
+    let result = match options.dir().and_then(|dir| KnowledgeBase::open(&dir)) {
+        Result::Ok(kb) => kb.query(http_client, embedding_options, query, options).await,
+        Err(e) => Err(e),
+    };
+    result.unwrap_or_else(|e| {
+        output::warn(msg!("kb.skipped", error = format!("{:#}", e)).yellow());
+        Vec::new()
+    })
+}
+
+// Saves fetched pages for the current mode, reporting (not failing on) errors.
+pub async fn save(http_client: &Client, embedding_options: &EmbeddingOptions, options: &KbOptions, sources: &[SearchSource]) {
+    if options.mode == KbMode::Off {
+        return;
+    }
+    let result = match options.dir().and_then(|dir| KnowledgeBase::open(&dir)) {
+        Result::Ok(mut kb) => kb.store(http_client, embedding_options, sources).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        output::warn(msg!("kb.save_error", error = format!("{:#}", e)).yellow());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    fn chunk(url: &str, text: &str) -> KbChunk {
+        KbChunk {
+            url: url.to_string(),
+            title: format!("Title of {}", url),
+            published_date: None,
+            fetched_at: "2024-06-15T00:00:00.000Z".to_string(),
+            content_hash: format!("{:016x}", fnv1a(text.as_bytes())),
+            text: text.to_string(),
+        }
+    }
+
+    fn texts(kb: &KnowledgeBase) -> Vec<&str> {
+        kb.chunks.iter().map(|chunk| chunk.text.as_str()).collect()
+    }
+
+    fn append_file(path: &Path, bytes: &[u8]) {
+        OpenOptions::new().append(true).open(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn missing_directory_is_empty() {
+        let dir = TempDir::new("kb-missing");
+        let kb = KnowledgeBase::open(&dir.0.join("kb")).unwrap();
+        assert_eq!(kb.page_count(), 0);
+        assert!(kb.meta.is_none());
+    }
+
+    #[test]
+    fn appends_round_trip() {
+        let dir = TempDir::new("kb-round-trip");
+        let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+        kb.append("model", vec![chunk("https://a.example", "one"), chunk("https://a.example", "two")], vec![vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap();
+
+        let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+        assert_eq!(kb.meta, Some(KbMeta { model: "model".to_string(), dimensions: 2 }));
+        assert_eq!(texts(&kb), ["one", "two"]);
+        assert_eq!(kb.vectors, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(kb.page_count(), 1);
+
+        kb.append("model", vec![chunk("https://b.example", "three")], vec![vec![5.0, 6.0]]).unwrap();
+        let kb = KnowledgeBase::open(&dir.0).unwrap();
+        assert_eq!(texts(&kb), ["one", "two", "three"]);
+        assert_eq!(kb.vectors, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(kb.page_count(), 2);
+    }
+
+    #[test]
+    fn mismatched_embeddings_are_rejected() {
+        let dir = TempDir::new("kb-mismatched");
+        let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+        kb.append("model", vec![chunk("https://a.example", "one")], vec![vec![1.0, 2.0]]).unwrap();
+        assert!(kb.append("model", vec![chunk("https://a.example", "two")], vec![vec![1.0, 2.0, 3.0]]).is_err());
+        assert!(kb.append("model", vec![chunk("https://a.example", "two")], Vec::new()).is_err());
+        assert_eq!(texts(&KnowledgeBase::open(&dir.0).unwrap()), ["one"]);
+    }
+
+    #[test]
+    fn recovers_from_interrupted_writes() {
+        let cases: [(&str, &[u8], &[u8]); 4] = [
+            // Vectors written, chunk lines not.
+            ("orphan-vectors", &[0; 8], b""),
+            // Half a vector, or half a float.
+            ("partial-vector", &[0; 6], b""),
+            // A chunk line cut off mid-write.
+            ("torn-line", b"", b"{\"url\":\"https://torn"),
+            // A complete chunk line without its vector.
+            ("orphan-chunk", b"", b"{\"url\":\"https://c.example\",\"title\":\"\",\"published_date\":null,\"fetched_at\":\"\",\"content_hash\":\"\",\"text\":\"orphan\"}\n"),
+        ];
+        for (name, vector_tail, chunk_tail) in cases {
+            let dir = TempDir::new(&format!("kb-{}", name));
+            let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+            kb.append("model", vec![chunk("https://a.example", "one")], vec![vec![1.0, 2.0]]).unwrap();
+            append_file(&dir.0.join("vectors.f32"), vector_tail);
+            append_file(&dir.0.join("chunks.jsonl"), chunk_tail);
+
+            let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+            assert_eq!(texts(&kb), ["one"], "{}", name);
+            assert_eq!(kb.vectors, [1.0, 2.0], "{}", name);
+
+            // The next append replaces the torn tail rather than landing after it.
+            kb.append("model", vec![chunk("https://b.example", "two")], vec![vec![3.0, 4.0]]).unwrap();
+            let kb = KnowledgeBase::open(&dir.0).unwrap();
+            assert_eq!(texts(&kb), ["one", "two"], "{}", name);
+            assert_eq!(kb.vectors, [1.0, 2.0, 3.0, 4.0], "{}", name);
+            assert_eq!(fs::metadata(dir.0.join("vectors.f32")).unwrap().len(), 16, "{}", name);
+        }
+    }
+
+    #[test]
+    fn corrupt_lines_before_the_end_are_errors() {
+        let dir = TempDir::new("kb-corrupt");
+        let mut kb = KnowledgeBase::open(&dir.0).unwrap();
+        kb.append("model", vec![chunk("https://a.example", "one")], vec![vec![1.0, 2.0]]).unwrap();
+        append_file(&dir.0.join("chunks.jsonl"), b"not json\n");
+        assert!(KnowledgeBase::open(&dir.0).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use serde::Deserialize;
+use whatlang::{Lang, Script};
+
+// Query language detection and the languages keywords and answers are written in.
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageOptions {
+    // Language of the answer: a name ("German"), a tag ("zh-CN"), or "query" for the question's
+    // language. Unset leaves it to the model.
+    pub answer: Option<String>,
+    // Generate keywords in each of these languages and search with every set, e.g. ["query", "en"].
+    // Empty leaves the keyword language to the model and searches once.
+    pub keywords: Vec<String>,
+}
+
+impl LanguageOptions {
+    // One entry per keyword set to generate; None lets the model pick the language.
+    pub fn keyword_languages(&self, query: &str) -> Vec<Option<String>> {
+        let mut languages: Vec<Option<String>> = Vec::new();
+        for spec in &self.keywords {
+            let language = resolve(spec, query);
+            if language.is_some() && !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        if languages.is_empty() {
+            languages.push(None);
+        }
+        languages
+    }
+}
+
+// The language name to put into a prompt for a language setting.
+pub fn resolve(spec: &str, query: &str) -> Option<String> {
+    let spec = spec.trim();
+    if spec.is_empty() || spec.eq_ignore_ascii_case("auto") {
+        None
+    } else if spec.eq_ignore_ascii_case("query") {
+        detect_language(query).map(str::to_string)
+    } else {
+        Some(language_name(spec).to_string())
+    }
+}
+
+// English name of the language a text is written in. Mixed queries such as "Rust 的 async 运行时"
+// are written in their non-Latin language even when the technical terms outnumber its
+// characters, and short Latin-script queries are too ambiguous for trigram statistics, so they
+// count as English unless detection is confident.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let non_ascii: String = text.chars().filter(|c| !c.is_ascii()).collect();
+    let info = whatlang::detect(&non_ascii).filter(|info| info.script() != Script::Latin)
+        .or_else(|| whatlang::detect(text))?;
+    match info.lang() {
+        Lang::Cmn => Some("Chinese"),
+        _ if info.script() == Script::Latin && info.confidence() < 0.5 => Some("English"),
+        lang => Some(lang.eng_name()),
+    }
+}
+
+// Maps common language tags to names models follow reliably; anything else is used as given.
+pub fn language_name(spec: &str) -> &str {
+    match spec.to_ascii_lowercase().replace('_', "-").as_str() {
+        "en" | "en-us" | "en-gb" => "English",
+        "zh" | "zh-cn" | "zh-sg" | "zh-hans" => "Simplified Chinese",
+        "zh-tw" | "zh-hk" | "zh-hant" => "Traditional Chinese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "de" => "German",
+        "fr" => "French",
+        "es" => "Spanish",
+        "pt" | "pt-br" => "Portuguese",
+        "it" => "Italian",
+        "ru" => "Russian",
+        _ => spec,
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::messages::UiLanguage;
+
+// Where and when the user is asking from, so prompts can mention today's date and the
+// user's time zone and locale instead of leaving the model to assume its training cutoff.
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocaleOptions {
+    // IANA time zone such as "Asia/Shanghai". Defaults to the system time zone.
+    pub timezone: Option<String>,
+    // BCP 47 language tag such as "zh-CN". Defaults to LC_ALL / LC_MESSAGES / LANG.
+    pub locale: Option<String>,
+    // Language of the terminal messages ("en" or "zh-CN"). Defaults to the locale's.
+    pub messages: Option<UiLanguage>,
+}
+
+impl LocaleOptions {
+    pub fn locale(&self) -> String {
+        self.locale.clone().or_else(system_locale).unwrap_or_else(|| "en-US".to_string())
+    }
+
+    pub fn messages(&self) -> UiLanguage {
+        self.messages.unwrap_or_else(|| UiLanguage::from_locale(&self.locale()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalContext {
+    pub now: DateTime<Tz>,
+    pub locale: String,
+}
+
+impl LocalContext {
+    pub fn detect(options: &LocaleOptions) -> Result<Self> {
+        let timezone = match options.timezone.as_deref() {
+            Some(name) => name.parse::<Tz>().map_err(|e| anyhow::anyhow!(e))
+                .with_context(|| format!("`{}` is not an IANA time zone name", name))?,
+            None => system_timezone(),
+        };
+        Ok(Self { now: Utc::now().with_timezone(&timezone), locale: options.locale() })
+    }
+
+    // The system time zone and locale, ignoring the config.
+    pub fn system() -> Self {
+        Self { now: Utc::now().with_timezone(&system_timezone()), locale: LocaleOptions::default().locale() }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now.date_naive()
+    }
+
+    pub fn date(&self) -> String {
+        self.now.format("%Y-%m-%d").to_string()
+    }
+
+    // e.g. "Asia/Shanghai (UTC+08:00)".
+    pub fn timezone(&self) -> String {
+        format!("{} (UTC{})", self.now.timezone().name(), self.now.format("%:z"))
+    }
+}
+
+fn system_timezone() -> Tz {
+    iana_time_zone::get_timezone().ok()
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+// The POSIX form "zh_CN.UTF-8@modifier" becomes "zh-CN"; "C" and "POSIX" mean no preference.
+fn system_locale() -> Option<String> {
+    let raw = sys_locale::get_locale()?;
+    let tag = raw.split(['.', '@']).next().unwrap_or_default().replace('_', "-");
+    (!tag.is_empty() && tag != "C" && tag != "POSIX").then_some(tag)
+}
@@ -1,7 +1,11 @@
 use anyhow::{Context, Result, Ok};
+use clap::{Parser, ValueEnum};
 use reqwest::Client;
+use std::collections::HashSet;
 use std::env;
-use std::io::{self, Write};
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
 use colored::Colorize; // Added for terminal styling
 
 mod models;
@@ -9,79 +13,741 @@ mod utils;
 mod openrouter_client;
 mod exa_client;
 mod constants;
+mod cli;
+mod output;
+mod markdown;
+mod config;
+mod context;
+mod corpus;
+mod cost;
+mod budget;
+mod agent;
+mod embeddings;
+mod rerank;
+mod kb;
+mod dedup;
+mod extract;
+mod fetcher;
+mod generation;
+mod prompts;
+mod provider;
+mod reasoning;
+mod structured;
+mod persona;
+mod locale;
+mod temporal;
+mod language;
+mod messages;
 
-use crate::openrouter_client::{generate_search_keywords, generate_final_answer};
-use crate::exa_client::fetch_exa_search_results;
+use crate::cli::{Cli, Command, IndexCommand, KbCommand, PromptsCommand};
+use crate::agent::AgentOptions;
+use crate::budget::BudgetOptions;
+use crate::config::Config;
+use crate::constants::open_router::SUMMARY_MODEL;
+use crate::context::ContextOptions;
+use crate::corpus::{CorpusIndex, search_corpus};
+use crate::dedup::DedupOptions;
+use crate::exa_client::{
+    SearchSource, apply_keyword_hints, fetch_exa_search_results, fetch_exa_similar_results,
+    fetch_exa_url_contents, render_search_results, render_sources_markdown,
+};
+use crate::fetcher::{FetchMode, FetchOptions, PageFetcher};
+use crate::generation::GenerationOptions;
+use crate::kb::{KbMode, KbOptions, KnowledgeBase};
+use crate::language::{LanguageOptions, detect_language};
+use crate::locale::LocalContext;
+use crate::messages::msg;
+use crate::models::{ExaSearchOptions, UsageInfo};
+use crate::prompts::{PromptName, Prompts};
+use crate::provider::ProviderPreferences;
+use crate::rerank::rerank_sources;
+use crate::structured::StructuredOptions;
+use crate::temporal::detect_recency;
+use crate::openrouter_client::{
+    final_answer_prompt, generate_critique, generate_final_answer, generate_follow_ups, generate_search_keywords,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
 
-    let openrouter_api_key = env::var("OPENROUTER_API_KEY")
-        .context("OPENROUTER_API_KEY not found in .env file")?;
-    let exa_api_key = env::var("EXA_API_KEY")
-        .context("EXA_API_KEY not found in .env file")?;
+    let cli = Cli::parse();
+    output::init(cli.verbosity(), cli.color, cli.raw);
+    let config = config::load(cli.config.as_deref(), cli.profile.as_deref())?;
+    messages::init(cli.ui_lang.unwrap_or_else(|| config.locale.messages()));
 
     let http_client = Client::new();
+    if !matches!(cli.command, Some(Command::Index { .. } | Command::Prompts { .. })) {
+        cost::init(&http_client, &config.cost).await;
+    }
+    budget::init(&budget_options(&cli, &config));
+    let provider_preferences = provider_options(&cli, &config);
+    if !provider_preferences.is_empty() {
+        output::detail(msg!("provider.preferences", preferences = provider_preferences.describe()).dimmed());
+    }
+    provider::init(provider_preferences);
+    let mut reasoning_options = config.reasoning.clone();
+    cli.generation.apply_reasoning(&mut reasoning_options);
+    reasoning::init(&reasoning_options);
+    structured::init(&structured_options(&cli, &config))?;
 
-    println!("{}", "Please input what you want in the next line...".yellow());
-    io::stdout().flush()?;
+    let result = match &cli.command {
+        None | Some(Command::Search { .. }) => run_search(&cli, &config, &http_client).await,
+        Some(Command::Similar { url, question }) => {
+            run_similar(&cli, &config, &http_client, url, &question.join(" ")).await
+        }
+        Some(Command::Summarize { urls, file, question }) => {
+            let urls = collect_urls(urls, file.as_deref())?;
+            let question = question.as_deref().unwrap_or(DEFAULT_SUMMARIZE_QUESTION);
+            run_summarize(&cli, &config, &http_client, &urls, question).await
+        }
+        Some(Command::Index { action }) => run_index(&config, action),
+        Some(Command::Kb { action: KbCommand::Query { question, answer, limit } }) => {
+            run_kb_query(&cli, &config, &http_client, &question.join(" "), *answer, *limit).await
+        }
+        Some(Command::Prompts { action }) => run_prompts(&config, action),
+    };
+    // Failed runs still spent money.
+    cost::finish_run(&config.cost);
+    result
+}
 
-    let mut user_query = String::new();
-    let _ = io::stdin().read_line(&mut user_query);
+// Default mode: keywords -> Exa search -> final answer.
+async fn run_search(cli: &Cli, config: &Config, http_client: &Client) -> Result<()> {
+    let openrouter_api_key = api_key("OPENROUTER_API_KEY")?;
+    let exa_api_key = api_key("EXA_API_KEY")?;
+
+    let user_query = read_user_query(cli)?;
     let user_query = user_query.trim();
 
     if user_query.is_empty() {
-        println!("{}", "Input data is empty. Please provide a query.".red());
+        output::error(msg!("input.query_empty").red());
         return Ok(());
     }
 
-    // 1. Generate search keywords
-    println!("{}", "🔍 Phase 1: Generating Search Keywords".bright_blue().bold());
-    let (search_keywords, keyword_usage) = match generate_search_keywords(&http_client, &openrouter_api_key, user_query).await {
-        Result::Ok(result) => result,
+    let prompts = prompts(cli, config)?;
+    let generation = generation_options(cli, config)?;
+    let context_options = context_options(cli, config);
+    let kb_options = kb_options(cli, config);
+    let kb_sources = kb::retrieve(http_client, &config.embeddings, &kb_options, user_query).await;
+    if kb_options.mode == KbMode::Prefer && !kb_sources.is_empty() && kb_sources.len() >= kb_options.min_results {
+        output::status(msg!("kb.answering", count = kb_sources.len()).bright_blue().bold());
+        output::status(render_search_results(&kb_sources));
+        output::status(format!("\n{}", msg!("phase.answer").bright_blue().bold()));
+        return answer_from_sources(cli, config, http_client, &openrouter_api_key, user_query, &kb_sources).await;
+    }
+
+    if let Some(language) = detect_language(user_query) {
+        output::detail(msg!("input.query_language", language = language).dimmed());
+    }
+    let agent_options = agent_options(cli, config);
+    if agent_options.enabled {
+        if structured::schema().is_some() {
+            return Err(anyhow::anyhow!(msg!("structured.with_agent")));
+        }
+        return run_agent(cli, config, http_client, &openrouter_api_key, &exa_api_key, user_query, &agent_options).await;
+    }
+
+    // 1. Generate search keywords, one set per keyword language
+    output::status(msg!("phase.keywords").bright_blue().bold());
+    let mut keyword_suggestions = Vec::new();
+    for language in language_options(cli, config).keyword_languages(user_query) {
+        let (keyword_suggestion, keyword_usage) = match generate_search_keywords(http_client, &openrouter_api_key, &prompts, &generation, user_query, language.as_deref()).await {
+            Result::Ok(result) => result,
+            Err(e) => {
+                output::error(msg!("keywords.error", error = format!("{:?}", e)).red());
+                return Err(e);
+            }
+        };
+        if let Some(usage) = keyword_usage {
+            output::status(msg!("keywords.usage",
+                prompt = usage.prompt_tokens, completion = usage.completion_tokens.unwrap_or(0), total = usage.total_tokens,
+                details = usage_details(config, &usage)).cyan());
+        }
+        keyword_suggestions.push(keyword_suggestion);
+    }
+    let search_queries: Vec<String> = keyword_suggestions.iter().map(|suggestion| suggestion.keywords.clone()).collect();
+
+    let mut search_options = search_options(cli, config, &prompts, user_query);
+    if config.keyword_hints && !cli.search.no_search_hints {
+        for hint in apply_keyword_hints(&mut search_options, &keyword_suggestions[0]) {
+            output::status(msg!("search.hint", hint = hint).cyan());
+        }
+    }
+
+    // 2. Fetch Exa search results
+    output::status(format!("\n{}", msg!("phase.search").bright_blue().bold()));
+    let content_options = context_options.exa_contents(user_query);
+    let fetch_options = fetch_options(cli, config);
+    let fetcher = PageFetcher::new(http_client, &fetch_options);
+    let exa_contents = (fetcher.mode() != FetchMode::Primary).then_some(&content_options);
+    let mut search_sources = match fetch_exa_search_results(http_client, &exa_api_key, &search_queries, &search_options, exa_contents, &dedup_options(cli, config)).await {
+        Result::Ok(sources) => sources,
         Err(e) => {
-            eprintln!("{}", format!("Error generating search keywords: {:?}", e).red());
+            output::error(msg!("exa.search_error", error = format!("{:?}", e)).red());
             return Err(e);
         }
     };
-    if let Some(usage) = keyword_usage {
-        println!("{}", format!("🔑 Keyword Generation Token Usage: Prompt: {}, Completion: {}, Total: {}", 
-            usage.prompt_tokens, usage.completion_tokens.unwrap_or(0), usage.total_tokens).cyan());
+    fetcher.complete_sources(&mut search_sources).await;
+    kb::save(http_client, &config.embeddings, &kb_options, &search_sources).await;
+    if !kb_sources.is_empty() {
+        output::status(msg!("kb.found", count = kb_sources.len()).cyan());
+        // A page that was just fetched again is fresher than its saved copy.
+        let fetched: HashSet<String> = search_sources.iter().map(|s| s.url.clone()).collect();
+        search_sources.extend(kb_sources.into_iter().filter(|s| !fetched.contains(&s.url)));
+    }
+    if config.corpus.enabled && !cli.search.no_corpus {
+        let corpus_sources = search_corpus(&config.corpus, &format!("{} {}", user_query, search_queries.join(" ")));
+        if !corpus_sources.is_empty() {
+            output::status(msg!("corpus.found", count = corpus_sources.len()).cyan());
+            search_sources.extend(corpus_sources);
+        }
     }
+    rerank(cli, config, http_client, user_query, &mut search_sources).await;
+    // Short listing for the terminal; the prompt gets a budgeted rendering.
+    output::status(render_search_results(&search_sources));
 
-    // 2. Fetch Exa search results
-    println!("\n{}", "🌐 Phase 2: Fetching Search Results (Exa)".bright_blue().bold());
-    let search_results_summary = match fetch_exa_search_results(&http_client, &exa_api_key, &search_keywords).await {
-        Result::Ok(summary) => summary,
+    // 3. Generate final answer
+    output::status(format!("\n{}", msg!("phase.answer.3").bright_blue().bold()));
+    answer_from_sources(cli, config, http_client, &openrouter_api_key, user_query, &search_sources).await
+}
+
+// Agent mode: the answer model runs web searches, page fetches and similar-page lookups as tool
+// calls until it can answer, in place of the keyword and search phases.
+async fn run_agent(
+    cli: &Cli,
+    config: &Config,
+    http_client: &Client,
+    openrouter_api_key: &str,
+    exa_api_key: &str,
+    user_query: &str,
+    agent_options: &AgentOptions,
+) -> Result<()> {
+    let prompts = prompts(cli, config)?;
+    let generation = generation_options(cli, config)?;
+    let context_options = context_options(cli, config);
+    let search_options = search_options(cli, config, &prompts, user_query);
+    let fetch_options = fetch_options(cli, config);
+    let fetcher = PageFetcher::new(http_client, &fetch_options);
+    let dedup_options = dedup_options(cli, config);
+    let tools = agent::Tools {
+        http_client,
+        exa_api_key,
+        search_options: &search_options,
+        context_options: &context_options,
+        dedup_options: &dedup_options,
+        fetcher: &fetcher,
+    };
+
+    output::status(msg!("phase.agent").bright_blue().bold());
+    let agent_answer = match agent::run(&tools, openrouter_api_key, &prompts, &generation, agent_options, user_query).await {
+        Result::Ok(agent_answer) => agent_answer,
         Err(e) => {
-            eprintln!("{}", format!("Error fetching Exa search results: {:?}", e).red());
-            return Err(e); 
+            output::error(msg!("answer.error", error = format!("{:?}", e)).red());
+            return Err(e);
         }
     };
-    // Print the formatted Exa search results summary
-    println!("{}", search_results_summary);
-    // Exa search results will be processed and displayed with URL, title, and summary.
-    // The function fetch_exa_search_results will be updated to return structured data.
+    if let Some(usage) = &agent_answer.usage {
+        output::status(format!("\n{}", msg!("answer.usage",
+            prompt = usage.prompt_tokens, completion = usage.completion_tokens.unwrap_or(0), total = usage.total_tokens,
+            details = usage_details(config, usage)).cyan()));
+    }
+    kb::save(http_client, &config.embeddings, &kb_options(cli, config), &agent_answer.sources).await;
+    if !agent_answer.sources.is_empty() {
+        output::status(msg!("agent.sources", count = agent_answer.sources.len()).cyan());
+        output::status(render_search_results(&agent_answer.sources));
+    }
 
-    // 3. Generate final answer
-    println!("\n{}", "💡 Phase 3: Generating Final Answer (OpenRouter)".bright_blue().bold());
-    match generate_final_answer(&http_client, &openrouter_api_key, user_query, &search_results_summary).await {
+    let search_results_summary = context::build_context(&agent_answer.sources, &context_options);
+    critique_and_follow_ups(cli, config, http_client, openrouter_api_key, user_query, &search_results_summary, &agent_answer.answer).await
+}
+
+// `similar <url> [question]`: Exa findSimilar -> list the pages, or answer the question over them.
+async fn run_similar(cli: &Cli, config: &Config, http_client: &Client, url: &str, question: &str) -> Result<()> {
+    reqwest::Url::parse(url).with_context(|| msg!("input.invalid_url", url = url))?;
+    let exa_api_key = api_key("EXA_API_KEY")?;
+    let question = question.trim();
+    // Only needed for the answer phase, so a missing key is fine when just listing.
+    let openrouter_api_key = if question.is_empty() { None } else { Some(api_key("OPENROUTER_API_KEY")?) };
+
+    let mut search_options = config.exa.clone();
+    cli.search.apply(&mut search_options);
+    let context_options = context_options(cli, config);
+    // Highlights and summaries are focused on the question, or on the seed article itself.
+    let focus = if question.is_empty() { url } else { question };
+    let content_options = context_options.exa_contents(focus);
+    let fetch_options = fetch_options(cli, config);
+    let fetcher = PageFetcher::new(http_client, &fetch_options);
+    let exa_contents = (fetcher.mode() != FetchMode::Primary).then_some(&content_options);
+
+    output::status(msg!("phase.similar").bright_blue().bold());
+    let mut similar_sources = match fetch_exa_similar_results(http_client, &exa_api_key, url, &search_options, exa_contents, &dedup_options(cli, config)).await {
+        Result::Ok(sources) => sources,
+        Err(e) => {
+            output::error(msg!("exa.similar_error", error = format!("{:?}", e)).red());
+            return Err(e);
+        }
+    };
+    fetcher.complete_sources(&mut similar_sources).await;
+    kb::save(http_client, &config.embeddings, &kb_options(cli, config), &similar_sources).await;
+    if !question.is_empty() {
+        rerank(cli, config, http_client, question, &mut similar_sources).await;
+    }
+
+    let Some(openrouter_api_key) = openrouter_api_key else {
+        output::answer(&render_sources_markdown(&similar_sources)).context("Failed to write results to stdout")?;
+        return Ok(());
+    };
+    output::status(render_search_results(&similar_sources));
+
+    output::status(format!("\n{}", msg!("phase.answer.2").bright_blue().bold()));
+    answer_from_sources(cli, config, http_client, &openrouter_api_key, question, &similar_sources).await
+}
+
+// `summarize <urls>`: page contents by URL -> final answer, with no keyword or search phase.
+async fn run_summarize(cli: &Cli, config: &Config, http_client: &Client, urls: &[String], question: &str) -> Result<()> {
+    let openrouter_api_key = api_key("OPENROUTER_API_KEY")?;
+    let context_options = context_options(cli, config);
+    let fetch_options = fetch_options(cli, config);
+    let fetcher = PageFetcher::new(http_client, &fetch_options);
+
+    // With the local fetcher as the primary source, Exa (and its API key) isn't needed at all.
+    let mut sources = if fetcher.mode() == FetchMode::Primary {
+        output::status(msg!("phase.fetch.direct", count = urls.len()).bright_blue().bold());
+        urls.iter().map(|url| SearchSource::from_url(url)).collect()
+    } else {
+        let exa_api_key = api_key("EXA_API_KEY")?;
+        let content_options = context_options.exa_contents(question);
+        output::status(msg!("phase.fetch.exa", count = urls.len()).bright_blue().bold());
+        match fetch_exa_url_contents(http_client, &exa_api_key, urls, &content_options).await {
+            Result::Ok(sources) => sources,
+            Err(e) => {
+                output::error(msg!("exa.contents_error", error = format!("{:?}", e)).red());
+                return Err(e);
+            }
+        }
+    };
+    fetcher.complete_sources(&mut sources).await;
+    kb::save(http_client, &config.embeddings, &kb_options(cli, config), &sources).await;
+
+    for source in sources.iter().filter(|s| !s.has_content()) {
+        output::warn(msg!("summarize.no_content", url = source.url).yellow());
+    }
+    if !sources.iter().any(SearchSource::has_content) {
+        return Err(anyhow::anyhow!(msg!("summarize.no_content_any")));
+    }
+    output::status(render_search_results(&sources));
+
+    output::status(format!("\n{}", msg!("phase.answer.2").bright_blue().bold()));
+    answer_from_sources(cli, config, http_client, &openrouter_api_key, question, &sources).await
+}
+
+// `index build [dir]` / `index update`: (re)builds the local corpus index.
+fn run_index(config: &Config, action: &IndexCommand) -> Result<()> {
+    let index_path = config.corpus.index_path()?;
+    let (index, stats) = match action {
+        IndexCommand::Build { dir } => {
+            let dir = dir.as_deref().or(config.corpus.path.as_deref())
+                .with_context(|| msg!("corpus.no_dir"))?;
+            output::status(msg!("corpus.building", dir = dir.display()).bright_blue().bold());
+            CorpusIndex::build(dir, None)?
+        }
+        IndexCommand::Update => {
+            let previous = CorpusIndex::load(&index_path).with_context(|| msg!("corpus.no_index"))?;
+            output::status(msg!("corpus.updating", dir = previous.root().display()).bright_blue().bold());
+            CorpusIndex::build(previous.root(), Some(&previous))?
+        }
+    };
+    index.save(&index_path)?;
+    output::status(msg!(
+        "corpus.done",
+        added = stats.added, changed = stats.changed, unchanged = stats.unchanged,
+        removed = stats.removed, skipped = stats.skipped, path = index_path.display(),
+    ).green());
+    Ok(())
+}
+
+// `kb query <question>`: lists the saved pages most similar to the question, or answers from them.
+async fn run_kb_query(cli: &Cli, config: &Config, http_client: &Client, question: &str, answer: bool, limit: Option<usize>) -> Result<()> {
+    let mut kb_options = kb_options(cli, config);
+    if let Some(limit) = limit {
+        kb_options.max_results = limit;
+    }
+    let openrouter_api_key = if answer { Some(api_key("OPENROUTER_API_KEY")?) } else { None };
+    let knowledge_base = KnowledgeBase::open(&kb_options.dir()?)?;
+    if knowledge_base.page_count() == 0 {
+        return Err(anyhow::anyhow!(msg!("kb.empty")));
+    }
+
+    output::status(msg!("kb.searching", count = knowledge_base.page_count()).bright_blue().bold());
+    let sources = knowledge_base.query(http_client, &config.embeddings, question, &kb_options).await?;
+    if sources.is_empty() {
+        output::warn(msg!("kb.no_match", threshold = kb_options.threshold).yellow());
+        return Ok(());
+    }
+
+    let Some(openrouter_api_key) = openrouter_api_key else {
+        output::answer(&render_sources_markdown(&sources)).context("Failed to write results to stdout")?;
+        return Ok(());
+    };
+    output::status(render_search_results(&sources));
+    output::status(format!("\n{}", msg!("phase.answer").bright_blue().bold()));
+    answer_from_sources(cli, config, http_client, &openrouter_api_key, question, &sources).await
+}
+
+// `prompts list|show|edit`: inspect and customize the prompt templates.
+fn run_prompts(config: &Config, action: &PromptsCommand) -> Result<()> {
+    let prompts = Prompts::new(&config.prompts);
+    match action {
+        PromptsCommand::List => {
+            for name in PromptName::value_variants() {
+                let location = match prompts.override_path(*name).filter(|_| prompts.is_overridden(*name)) {
+                    Some(path) => msg!("prompts.overridden", path = path.display()).yellow(),
+                    None => msg!("prompts.built_in").dimmed(),
+                };
+                println!("{:<10} {} ({})", name.as_str().bold(), name.description(), location);
+            }
+            if let Some(dir) = prompts.dir() {
+                output::status(format!("\n{}", msg!("prompts.overrides_dir", dir = dir.display())).dimmed());
+            }
+            println!("\n{}", msg!("prompts.personas").bold());
+            for (name, system_prompt) in config.persona.presets() {
+                println!("{:<18} {}", name.bold(), system_prompt);
+            }
+        }
+        PromptsCommand::Show { name, default } => {
+            let source = if *default { name.default_source().into() } else { prompts.source(*name)? };
+            print!("{}", source);
+            if !source.ends_with('\n') {
+                println!();
+            }
+        }
+        PromptsCommand::Edit { name } => {
+            let path = prompts.override_path(*name)
+                .with_context(|| msg!("prompts.no_dir"))?;
+            if !path.is_file() {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+                }
+                fs::write(&path, name.default_source()).with_context(|| format!("Failed to write {}", path.display()))?;
+                output::status(msg!("prompts.copied", name = name.as_str(), path = path.display()).cyan());
+            }
+            open_editor(&path)?;
+            let source = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            Prompts::check(&source, *name)?;
+            output::status(msg!("prompts.saved", name = name.as_str(), path = path.display()).green());
+        }
+    }
+    Ok(())
+}
+
+// Opens the file in $VISUAL or $EDITOR and waits for the editor to exit.
+fn open_editor(path: &Path) -> Result<()> {
+    let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| fallback.to_string());
+    // The variable may carry arguments, e.g. `code --wait`.
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or(fallback);
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start the editor `{}`", editor))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("The editor `{}` exited with {}", editor, status));
+    }
+    Ok(())
+}
+
+// Optional embedding rerank; a failure only costs the reordering, not the run.
+async fn rerank(cli: &Cli, config: &Config, http_client: &Client, query: &str, sources: &mut Vec<SearchSource>) {
+    let mut rerank_options = config.rerank.clone();
+    cli.rerank.apply(&mut rerank_options);
+    if !rerank_options.enabled {
+        return;
+    }
+    if let Err(e) = rerank_sources(http_client, &config.embeddings, &rerank_options, query, sources).await {
+        output::warn(msg!("rerank.failed", error = format!("{:#}", e)).yellow());
+    }
+}
+
+// Builds the budgeted context from the sources and streams the final answer to stdout,
+// followed by the follow-up questions and critique when asked for.
+async fn answer_from_sources(
+    cli: &Cli,
+    config: &Config,
+    http_client: &Client,
+    openrouter_api_key: &str,
+    user_query: &str,
+    sources: &[SearchSource],
+) -> Result<()> {
+    let prompts = prompts(cli, config)?;
+    let generation = generation_options(cli, config)?;
+    let search_results_summary = fit_context_to_budget(&prompts, user_query, sources, &context_options(cli, config))?;
+    output::detail(msg!("answer.context_tokens", tokens = context::estimate_tokens(&search_results_summary)).dimmed());
+    if let Some(schema) = structured::schema() {
+        return answer_structured(cli, config, http_client, openrouter_api_key, user_query, &search_results_summary, schema).await;
+    }
+
+    let final_answer = match generate_final_answer(http_client, openrouter_api_key, &prompts, &generation, user_query, &search_results_summary).await {
         Result::Ok((final_answer, final_usage)) => {
-            println!("\n{}", "Final Answer:".bright_green().bold());
-            println!("{}", final_answer);
-            // The final_answer is streamed directly by handle_openrouter_stream if stream_to_stdout is true.
-            // No need to print it here again as it's displayed in real-time.
+            // The final answer has already been streamed to stdout by handle_openrouter_response.
             if let Some(usage) = final_usage {
-                println!("\n{}", format!("💬 Final Answer Token Usage: Prompt: {}, Completion: {}, Total: {}", 
-                    usage.prompt_tokens, usage.completion_tokens.unwrap_or(0), usage.total_tokens).cyan());
+                output::status(format!("\n{}", msg!("answer.usage",
+                    prompt = usage.prompt_tokens, completion = usage.completion_tokens.unwrap_or(0), total = usage.total_tokens,
+                    details = usage_details(config, &usage)).cyan()));
             }
+            final_answer
         }
         Err(e) => {
-            eprintln!("{}", format!("Error generating final answer: {:?}", e).red());
+            output::error(msg!("answer.error", error = format!("{:?}", e)).red());
             return Err(e);
         }
+    };
+
+    critique_and_follow_ups(cli, config, http_client, openrouter_api_key, user_query, &search_results_summary, &final_answer).await
+}
+
+// Structured mode: the answer as a JSON document matching the schema, written to stdout once it validates.
+async fn answer_structured(
+    cli: &Cli,
+    config: &Config,
+    http_client: &Client,
+    openrouter_api_key: &str,
+    user_query: &str,
+    search_results_summary: &str,
+    schema: &structured::Schema,
+) -> Result<()> {
+    if cli.answer.critique || cli.answer.follow_ups {
+        output::warn(msg!("structured.extras_skipped").yellow());
+    }
+    let prompts = prompts(cli, config)?;
+    let generation = generation_options(cli, config)?;
+    let structured_answer = match structured::answer(http_client, openrouter_api_key, &prompts, &generation, schema, user_query, search_results_summary).await {
+        Result::Ok(structured_answer) => structured_answer,
+        Err(e) => {
+            output::error(msg!("answer.error", error = format!("{:?}", e)).red());
+            return Err(e);
+        }
+    };
+    let document = serde_json::to_string_pretty(&structured_answer.document)?;
+    output::document(&document).context("Failed to write the answer to stdout")?;
+    if let Some(usage) = &structured_answer.usage {
+        output::status(format!("\n{}", msg!("answer.usage",
+            prompt = usage.prompt_tokens, completion = usage.completion_tokens.unwrap_or(0), total = usage.total_tokens,
+            details = usage_details(config, usage)).cyan()));
     }
+    Ok(())
+}
 
+// The critique and follow-up questions after an answer, when asked for.
+async fn critique_and_follow_ups(
+    cli: &Cli,
+    config: &Config,
+    http_client: &Client,
+    openrouter_api_key: &str,
+    user_query: &str,
+    search_results_summary: &str,
+    final_answer: &str,
+) -> Result<()> {
+    if !cli.answer.critique && !cli.answer.follow_ups {
+        return Ok(());
+    }
+    let prompts = prompts(cli, config)?;
+    let generation = generation_options(cli, config)?;
+    if cli.answer.critique {
+        let (critique, _) = generate_critique(http_client, openrouter_api_key, &prompts, &generation, user_query, search_results_summary, final_answer).await?;
+        output::answer(&format!("\n## {}\n\n{}\n", msg!("critique.heading"), critique)).context("Failed to write the critique to stdout")?;
+    }
+    if cli.answer.follow_ups {
+        let (questions, _) = generate_follow_ups(http_client, openrouter_api_key, &prompts, &generation, user_query, final_answer).await?;
+        let list: String = questions.iter().map(|question| format!("- {}\n", question)).collect();
+        output::answer(&format!("\n## {}\n\n{}", msg!("follow_ups.heading"), list)).context("Failed to write the follow-up questions to stdout")?;
+    }
     Ok(())
 }
+
+// The search-results section for the answer. When degrading over a budget, keeps halving the
+// sources (and the context budget with them) until the answer request fits or `min_sources` is reached.
+fn fit_context_to_budget(prompts: &Prompts, user_query: &str, sources: &[SearchSource], context_options: &ContextOptions) -> Result<String> {
+    let mut search_results_summary = context::build_context(sources, context_options);
+    if !budget::degrading() {
+        return Ok(search_results_summary);
+    }
+    let fits = |summary: &str| -> Result<bool> {
+        let final_prompt = final_answer_prompt(prompts, user_query, summary)?;
+        Ok(budget::fits(SUMMARY_MODEL, budget::estimate_request(prompts.system(), &final_prompt)))
+    };
+    let mut kept = sources.len();
+    let mut trimmed_options = context_options.clone();
+    while kept > budget::min_sources() && !fits(&search_results_summary)? {
+        let keep = (kept / 2).max(budget::min_sources());
+        trimmed_options.token_budget = trimmed_options.token_budget * keep / kept;
+        kept = keep;
+        search_results_summary = context::build_context(&sources[..kept], &trimmed_options);
+    }
+    if kept < sources.len() {
+        output::warn(msg!("budget.degraded_sources", count = kept, total = sources.len()).yellow());
+    }
+    Ok(search_results_summary)
+}
+
+// Reasoning tokens and cost, appended to a usage line.
+fn usage_details(config: &Config, usage: &UsageInfo) -> String {
+    let reasoning = match usage.reasoning_tokens() {
+        Some(tokens) if tokens > 0 => msg!("usage.reasoning", tokens = tokens),
+        _ => String::new(),
+    };
+    format!("{}{}", reasoning, cost::usage_suffix(&config.cost, usage))
+}
+
+fn api_key(name: &str) -> Result<String> {
+    env::var(name).with_context(|| msg!("input.missing_key", name = name))
+}
+
+// The prompt templates, with the persona's system prompt, the user's date and locale, and the
+// answer language.
+fn prompts(cli: &Cli, config: &Config) -> Result<Prompts> {
+    let mut persona_options = config.persona.clone();
+    cli.persona.apply(&mut persona_options);
+    Ok(Prompts::new(&config.prompts)
+        .with_system(persona_options.system_prompt()?)
+        .with_local(LocalContext::detect(&config.locale)?)
+        .with_answer_language(language_options(cli, config).answer))
+}
+
+fn generation_options(cli: &Cli, config: &Config) -> Result<GenerationOptions> {
+    let mut generation_options = config.generation.clone();
+    cli.generation.apply(&mut generation_options);
+    generation_options.validate()?;
+    Ok(generation_options)
+}
+
+// The Exa search options, restricted to recent pages when the question asks for them.
+fn search_options(cli: &Cli, config: &Config, prompts: &Prompts, user_query: &str) -> ExaSearchOptions {
+    let mut search_options = config.exa.clone();
+    cli.search.apply(&mut search_options);
+    if config.auto_recency && !cli.search.no_auto_recency && search_options.start_published_date.is_none()
+        && let Some(recency) = detect_recency(user_query, prompts.local().today())
+    {
+        let since = recency.since.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        search_options.start_published_date = Some(config::format_published_date(since));
+        output::status(msg!("search.recency", since = recency.since, cue = recency.cue).cyan());
+    }
+    search_options
+}
+
+fn structured_options(cli: &Cli, config: &Config) -> StructuredOptions {
+    let mut structured_options = config.structured.clone();
+    cli.structured.apply(&mut structured_options);
+    structured_options
+}
+
+fn agent_options(cli: &Cli, config: &Config) -> AgentOptions {
+    let mut agent_options = config.agent.clone();
+    cli.agent.apply(&mut agent_options);
+    agent_options
+}
+
+fn provider_options(cli: &Cli, config: &Config) -> ProviderPreferences {
+    let mut provider_preferences = config.provider.clone();
+    cli.provider.apply(&mut provider_preferences);
+    provider_preferences
+}
+
+fn budget_options(cli: &Cli, config: &Config) -> BudgetOptions {
+    let mut budget_options = config.budget.clone();
+    cli.budget.apply(&mut budget_options);
+    budget_options
+}
+
+fn language_options(cli: &Cli, config: &Config) -> LanguageOptions {
+    let mut language_options = config.language.clone();
+    cli.language.apply(&mut language_options);
+    language_options
+}
+
+fn context_options(cli: &Cli, config: &Config) -> ContextOptions {
+    let mut context_options = config.context.clone();
+    cli.context.apply(&mut context_options);
+    context_options
+}
+
+fn fetch_options(cli: &Cli, config: &Config) -> FetchOptions {
+    let mut fetch_options = config.fetch.clone();
+    cli.fetch.apply(&mut fetch_options);
+    fetch_options
+}
+
+fn dedup_options(cli: &Cli, config: &Config) -> DedupOptions {
+    let mut dedup_options = config.dedup.clone();
+    if cli.search.no_dedup {
+        dedup_options.enabled = false;
+    }
+    dedup_options
+}
+
+fn kb_options(cli: &Cli, config: &Config) -> KbOptions {
+    let mut kb_options = config.kb.clone();
+    cli.kb.apply(&mut kb_options);
+    kb_options
+}
+
+const DEFAULT_SUMMARIZE_QUESTION: &str =
+    "Summarize these pages and synthesize their key points, noting where they agree or disagree.";
+
+// URLs come from the arguments, then --file (`-` meaning stdin), then piped stdin.
+fn collect_urls(args: &[String], file: Option<&Path>) -> Result<Vec<String>> {
+    let mut raw = args.join("\n");
+    match file {
+        Some(path) if path == Path::new("-") => {
+            io::stdin().lock().read_to_string(&mut raw).context("Failed to read URLs from stdin")?;
+        }
+        Some(path) => {
+            raw.push('\n');
+            raw.push_str(&fs::read_to_string(path).with_context(|| format!("Failed to read URL file {}", path.display()))?);
+        }
+        None if args.is_empty() => {
+            if io::stdin().is_terminal() {
+                output::status(msg!("input.paste_urls").yellow());
+            }
+            io::stdin().lock().read_to_string(&mut raw).context("Failed to read URLs from stdin")?;
+        }
+        None => {}
+    }
+
+    let mut urls: Vec<String> = Vec::new();
+    for line in raw.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        for url in line.split_whitespace() {
+            reqwest::Url::parse(url).with_context(|| msg!("input.invalid_url", url = url))?;
+            if !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    if urls.is_empty() {
+        return Err(anyhow::anyhow!(msg!("input.no_urls")));
+    }
+    Ok(urls)
+}
+
+// The query comes from the command line, an interactive prompt, or piped stdin (in that order).
+fn read_user_query(cli: &Cli) -> Result<String> {
+    let query = match &cli.command {
+        Some(Command::Search { query }) => query,
+        _ => &cli.query,
+    };
+    if !query.is_empty() {
+        return Ok(query.join(" "));
+    }
+
+    let stdin = io::stdin();
+    let mut user_query = String::new();
+    if stdin.is_terminal() {
+        output::status(msg!("input.query").yellow());
+        stdin.read_line(&mut user_query).context("Failed to read query from stdin")?;
+    } else {
+        stdin.lock().read_to_string(&mut user_query).context("Failed to read query from stdin")?;
+    }
+    Ok(user_query)
+}
@@ -0,0 +1,763 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{LinesWithEndings, as_24_bit_terminal_escaped};
+use unicode_width::UnicodeWidthStr;
+
+// Terminal renderer for the Markdown the LLM answers with.
+// Styles are written as raw SGR sequences (not via `colored`) because the answer goes to
+// stdout, whose color support can differ from stderr's.
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const ITALIC: &str = "3";
+const UNDERLINE: &str = "4";
+const STRIKE: &str = "9";
+const YELLOW: &str = "33";
+const BLUE: &str = "34";
+const MAGENTA: &str = "35";
+const CYAN: &str = "36";
+
+const CODE_THEME: &str = "base16-ocean.dark";
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    pub color: bool,
+    pub hyperlinks: bool, // OSC-8 links instead of "text (url)"
+    pub width: usize,
+}
+
+pub fn render(markdown: &str, options: RenderOptions) -> String {
+    let mut renderer = Renderer::new(options);
+    for event in Parser::new_ext(markdown, parser_options()) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+// Renders Markdown as it streams in. Text is buffered until a block is known to be complete
+// (a blank line outside a code fence that isn't followed by list or indented content), so
+// code blocks, tables and lists are always rendered whole.
+pub struct StreamRenderer {
+    options: RenderOptions,
+    pending: String,
+    rendered_any: bool,
+}
+
+impl StreamRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options, pending: String::new(), rendered_any: false }
+    }
+
+    pub fn push(&mut self, delta: &str) -> String {
+        self.pending.push_str(delta);
+        match complete_prefix_len(&self.pending) {
+            Some(end) => {
+                let blocks: String = self.pending.drain(..end).collect();
+                self.render_chunk(&blocks)
+            }
+            None => String::new(),
+        }
+    }
+
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        self.render_chunk(&rest)
+    }
+
+    fn render_chunk(&mut self, markdown: &str) -> String {
+        if markdown.trim().is_empty() {
+            return String::new();
+        }
+        let mut rendered = render(markdown, self.options);
+        if self.rendered_any && !rendered.is_empty() {
+            rendered.insert(0, '\n');
+        }
+        self.rendered_any |= !rendered.is_empty();
+        rendered
+    }
+}
+
+// Returns the byte length of the longest prefix made of complete blocks, if any.
+fn complete_prefix_len(text: &str) -> Option<usize> {
+    let mut fence: Option<String> = None;
+    let mut candidate: Option<usize> = None;
+    let mut boundary = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break; // the last line is still being streamed
+        }
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = &fence {
+            let closes = trimmed.starts_with(marker.as_str())
+                && trimmed.trim_start_matches(marker.chars().next().unwrap()).trim().is_empty();
+            if closes {
+                fence = None;
+                candidate = Some(offset);
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            candidate = Some(offset);
+            continue;
+        }
+
+        if let Some(end) = candidate.take() {
+            let indented = line.starts_with(' ') || line.starts_with('\t');
+            if !indented && !is_list_item(trimmed) {
+                boundary = Some(end.min(line_start));
+            }
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let fence_char = trimmed.chars().next().unwrap();
+            let marker: String = trimmed.chars().take_while(|c| *c == fence_char).collect();
+            fence = Some(marker);
+        }
+    }
+    boundary
+}
+
+fn is_list_item(line: &str) -> bool {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+struct CodeBlock {
+    language: String,
+    text: String,
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    header_rows: usize,
+    cell: String,
+}
+
+struct Link {
+    url: String,
+    text: String,
+    image: bool,
+}
+
+struct Renderer {
+    options: RenderOptions,
+    out: String,
+    styles: Vec<&'static str>,
+    quote_depth: usize,
+    lists: Vec<Option<u64>>,
+    indents: Vec<usize>,
+    pending_marker: Option<String>,
+    at_line_start: bool,
+    pending_gap: bool,
+    links: Vec<Link>,
+    code: Option<CodeBlock>,
+    table: Option<Table>,
+}
+
+impl Renderer {
+    fn new(options: RenderOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            styles: Vec::new(),
+            quote_depth: 0,
+            lists: Vec::new(),
+            indents: Vec::new(),
+            pending_marker: None,
+            at_line_start: true,
+            pending_gap: false,
+            links: Vec::new(),
+            code: None,
+            table: None,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        self.out
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => self.inline_code(&code),
+            Event::InlineMath(math) | Event::DisplayMath(math) => self.text(&math),
+            Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+            Event::FootnoteReference(name) => self.text(&format!("[^{}]", name)),
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(table) = &mut self.table {
+                    table.cell.push(' ');
+                } else {
+                    self.newline();
+                }
+            }
+            Event::Rule => {
+                self.start_block();
+                let rule = "─".repeat(self.options.width.clamp(3, 80));
+                let rule = self.paint(&rule, DIM);
+                self.write(&rule);
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = match (checked, self.options.color) {
+                    (true, true) => "☑ ",
+                    (false, true) => "☐ ",
+                    (true, false) => "[x] ",
+                    (false, false) => "[ ] ",
+                };
+                self.write(marker);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                let (marker_style, text_styles): (&str, &[&'static str]) = match level {
+                    HeadingLevel::H1 => (MAGENTA, &[BOLD, MAGENTA, UNDERLINE]),
+                    HeadingLevel::H2 => (CYAN, &[BOLD, CYAN]),
+                    HeadingLevel::H3 => (BLUE, &[BOLD, BLUE]),
+                    _ => (DIM, &[BOLD]),
+                };
+                let marker = format!("{} ", "#".repeat(level as usize));
+                let marker = self.paint(&marker, marker_style);
+                self.write(&marker);
+                for style in text_styles {
+                    self.push_style(style);
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.start_block();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some(CodeBlock { language, text: String::new() });
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.end_line();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.end_line();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let marker = format!("{}. ", number);
+                        *number += 1;
+                        marker
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.indents.push(marker.width());
+                self.pending_marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table { alignments, rows: Vec::new(), header_rows: 0, cell: String::new() });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = &mut self.table {
+                    table.cell.clear();
+                }
+            }
+            Tag::Emphasis => self.push_style(ITALIC),
+            Tag::Strong => self.push_style(BOLD),
+            Tag::Strikethrough => self.push_style(STRIKE),
+            Tag::Link { dest_url, .. } => self.start_link(dest_url.to_string(), false),
+            Tag::Image { dest_url, .. } => self.start_link(dest_url.to_string(), true),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::Heading(_) => {
+                self.styles.clear();
+                self.apply_styles();
+                self.end_block();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.end_line();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.pending_gap = true;
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code.take() {
+                    self.write_code_block(code);
+                }
+                self.end_block();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                self.end_line();
+                if self.lists.is_empty() {
+                    self.pending_gap = true;
+                }
+            }
+            TagEnd::Item => {
+                self.end_line();
+                self.indents.pop();
+                self.pending_marker = None;
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.write_table(table);
+                }
+                self.end_block();
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table {
+                    let cell = std::mem::take(&mut table.cell);
+                    if let Some(row) = table.rows.last_mut() {
+                        row.push(cell.trim().to_string());
+                    }
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+            TagEnd::Link | TagEnd::Image => self.end_link(),
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(code) = &mut self.code {
+            code.text.push_str(text);
+        } else if let Some(table) = &mut self.table {
+            table.cell.push_str(text);
+        } else {
+            if let Some(link) = self.links.last_mut() {
+                link.text.push_str(text);
+            }
+            self.write(text);
+        }
+    }
+
+    fn inline_code(&mut self, code: &str) {
+        if let Some(table) = &mut self.table {
+            table.cell.push_str(code);
+        } else if self.options.color {
+            let painted = self.paint(code, YELLOW);
+            self.write(&painted);
+            self.apply_styles();
+        } else {
+            self.write(&format!("`{}`", code));
+        }
+    }
+
+    fn start_link(&mut self, url: String, image: bool) {
+        if image {
+            self.write("🖼 ");
+        }
+        if self.options.hyperlinks && self.table.is_none() {
+            self.out.push_str(&format!("\x1b]8;;{}\x1b\\", url));
+        }
+        self.push_style(UNDERLINE);
+        self.push_style(BLUE);
+        self.links.push(Link { url, text: String::new(), image });
+    }
+
+    fn end_link(&mut self) {
+        self.pop_style();
+        self.pop_style();
+        let Some(link) = self.links.pop() else { return };
+        if self.table.is_some() {
+            return;
+        }
+        if self.options.hyperlinks {
+            self.out.push_str("\x1b]8;;\x1b\\");
+        } else if link.image || (link.text != link.url && !link.url.starts_with('#')) {
+            let suffix = self.paint(&format!(" ({})", link.url), DIM);
+            self.write(&suffix);
+            self.apply_styles();
+        }
+    }
+
+    fn write_code_block(&mut self, code: CodeBlock) {
+        let fence = self.paint(&format!("```{}", code.language), DIM);
+        self.write(&fence);
+        self.newline();
+
+        let syntaxes = syntax_set();
+        let syntax = if code.language.is_empty() {
+            None
+        } else {
+            syntaxes.find_syntax_by_token(&code.language)
+        };
+        match (self.options.color, syntax, theme_set().themes.get(CODE_THEME)) {
+            (true, Some(syntax), Some(theme)) => {
+                let mut highlighter = HighlightLines::new(syntax, theme);
+                for line in LinesWithEndings::from(&code.text) {
+                    let highlighted = match highlighter.highlight_line(line, syntaxes) {
+                        Ok(ranges) => as_24_bit_terminal_escaped(&ranges, false),
+                        Err(_) => line.to_string(),
+                    };
+                    self.write(highlighted.trim_end_matches('\n'));
+                    self.out.push_str(RESET);
+                    self.newline();
+                }
+            }
+            _ => {
+                for line in code.text.lines() {
+                    self.write(line);
+                    self.newline();
+                }
+            }
+        }
+
+        let fence = self.paint("```", DIM);
+        self.write(&fence);
+    }
+
+    fn write_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.width());
+            }
+        }
+
+        let border = |left: &str, middle: &str, right: &str| -> String {
+            let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            format!("{}{}{}", left, segments.join(middle), right)
+        };
+        let top = self.paint(&border("┌", "┬", "┐"), DIM);
+        let separator = self.paint(&border("├", "┼", "┤"), DIM);
+        let bottom = self.paint(&border("└", "┴", "┘"), DIM);
+        let bar = self.paint("│", DIM);
+
+        self.write(&top);
+        self.newline();
+        for (row_index, row) in table.rows.iter().enumerate() {
+            let mut line = bar.clone();
+            for (i, width) in widths.iter().enumerate() {
+                let cell = row.get(i).map(String::as_str).unwrap_or("");
+                let padding = width - cell.width();
+                let (left, right) = match table.alignments.get(i) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                let cell = if row_index < table.header_rows { self.paint(cell, BOLD) } else { cell.to_string() };
+                line.push_str(&format!(" {}{}{} {}", " ".repeat(left), cell, " ".repeat(right), bar));
+            }
+            self.write(&line);
+            self.newline();
+            if row_index + 1 == table.header_rows && table.rows.len() > table.header_rows {
+                self.write(&separator);
+                self.newline();
+            }
+        }
+        self.write(&bottom);
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.options.color && !text.is_empty() {
+            format!("\x1b[{}m{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn push_style(&mut self, style: &'static str) {
+        self.styles.push(style);
+        self.apply_styles();
+    }
+
+    fn pop_style(&mut self) {
+        self.styles.pop();
+        self.apply_styles();
+    }
+
+    fn apply_styles(&mut self) {
+        if !self.options.color || self.table.is_some() {
+            return;
+        }
+        self.out.push_str(RESET);
+        if !self.styles.is_empty() {
+            self.out.push_str(&format!("\x1b[{}m", self.styles.join(";")));
+        }
+    }
+
+    // Writes text, emitting the blockquote/list prefix at the start of each line.
+    fn write(&mut self, text: &str) {
+        for (i, segment) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+            if segment.is_empty() {
+                continue;
+            }
+            if self.at_line_start {
+                self.write_prefix();
+            }
+            self.out.push_str(segment);
+        }
+    }
+
+    fn write_prefix(&mut self) {
+        let mut prefix = self.quote_prefix();
+        let indent: usize = self.indents.iter().sum();
+        match self.pending_marker.take() {
+            Some(marker) => {
+                prefix.push_str(&" ".repeat(indent.saturating_sub(marker.width())));
+                prefix.push_str(&self.paint(&marker, YELLOW));
+            }
+            None => prefix.push_str(&" ".repeat(indent)),
+        }
+        self.out.push_str(&prefix);
+        self.at_line_start = false;
+        if !self.styles.is_empty() {
+            self.apply_styles();
+        }
+    }
+
+    fn quote_prefix(&self) -> String {
+        self.paint(&"│ ".repeat(self.quote_depth), DIM)
+    }
+
+    fn newline(&mut self) {
+        if self.options.color && !self.styles.is_empty() {
+            self.out.push_str(RESET);
+        }
+        self.out.push('\n');
+        self.at_line_start = true;
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start {
+            self.newline();
+        }
+    }
+
+    fn start_block(&mut self) {
+        self.end_line();
+        if self.pending_gap && !self.out.is_empty() {
+            let gap = self.quote_prefix();
+            self.out.push_str(gap.trim_end());
+            self.out.push('\n');
+        }
+        self.pending_gap = false;
+    }
+
+    fn end_block(&mut self) {
+        self.end_line();
+        self.pending_gap = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "# Rust 2024
+
+Rust is a **systems** language with *zero-cost* abstractions, `cargo` and [docs](https://doc.rust-lang.org).
+The paragraph continues on a second line.
+
+## Install
+
+1. Download rustup
+2. Run the installer
+
+   It asks a few questions.
+3. Restart the shell
+
+- Tight item
+  - Nested item
+- Another item
+
+```rust
+fn main() {
+
+    println!(\"hello\");
+}
+```
+
+~~~text
+``` not a closing fence
+~~~
+
+| Edition | Year | Notes |
+|:--------|-----:|:-----:|
+| 2018 | 2018 | modules |
+| 2021 | 2021 | 闭包捕获 |
+
+> A quote
+>
+> spanning two paragraphs
+
+---
+
+- [x] done
+- [ ] todo
+
+最后一段中文。
+";
+
+    const PLAIN: RenderOptions = RenderOptions { color: false, hyperlinks: false, width: 40 };
+    const STYLED: RenderOptions = RenderOptions { color: true, hyperlinks: true, width: 40 };
+
+    fn stream(markdown: &str, options: RenderOptions, chunks: impl IntoIterator<Item = usize>) -> String {
+        let chars: Vec<char> = markdown.chars().collect();
+        let mut renderer = StreamRenderer::new(options);
+        let mut output = String::new();
+        let mut start = 0;
+        for size in chunks {
+            if start >= chars.len() {
+                break;
+            }
+            let end = (start + size.max(1)).min(chars.len());
+            output.push_str(&renderer.push(&chars[start..end].iter().collect::<String>()));
+            start = end;
+        }
+        output.push_str(&renderer.push(&chars[start..].iter().collect::<String>()));
+        output.push_str(&renderer.finish());
+        output
+    }
+
+    // Deterministic chunk sizes between 1 and `max` characters.
+    fn random_chunks(seed: u64, max: u64) -> impl Iterator<Item = usize> {
+        let mut state = seed;
+        std::iter::repeat_with(move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) % max + 1) as usize
+        })
+    }
+
+    #[test]
+    fn complete_prefixes() {
+        let cases = [
+            ("", None),
+            ("Still streaming", None),
+            ("One paragraph\n", None),
+            ("One paragraph\n\n", None),
+            ("One paragraph\n\nNext", None),
+            ("One paragraph\n\nNext\n", Some(15)),
+            ("# Title\n\nText\n", Some(9)),
+            // Blank lines inside a code fence don't end the block.
+            ("```\ncode\n\nmore\n", None),
+            ("```\ncode\n\nmore\n```\n", None),
+            ("```\ncode\n\nmore\n```\nAfter\n", Some(19)),
+            ("~~~\n```\n~~~\nAfter\n", Some(12)),
+            ("````\n```\n\n````\n\nAfter\n", Some(16)),
+            // List items and indented content after a blank line continue the list.
+            ("- one\n\n- two\n", None),
+            ("1. one\n\n2. two\n", None),
+            ("- one\n\n  more of one\n", None),
+            ("- one\n\n- two\n\nAfter\n", Some(14)),
+            // Tables end at a blank line like any other block.
+            ("| a | b |\n|---|---|\n| 1 | 2 |\n\nAfter\n", Some(31)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(complete_prefix_len(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn list_items() {
+        let cases = [
+            ("- item", true),
+            ("* item", true),
+            ("+ item", true),
+            ("12. item", true),
+            ("3) item", true),
+            ("-item", false),
+            ("1.5 apples", false),
+            ("Item", false),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(is_list_item(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn streaming_matches_a_single_render_for_fixed_chunk_sizes() {
+        for options in [PLAIN, STYLED] {
+            let expected = render(DOCUMENT, options);
+            // Larger chunks only split the document in fewer places.
+            for size in (1..=64).chain([DOCUMENT.len()]) {
+                assert_eq!(stream(DOCUMENT, options, std::iter::repeat(size)), expected, "chunks of {} characters", size);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_matches_a_single_render_for_random_chunk_sizes() {
+        for options in [PLAIN, STYLED] {
+            let expected = render(DOCUMENT, options);
+            for seed in 0..100 {
+                assert_eq!(stream(DOCUMENT, options, random_chunks(seed, 40)), expected, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_are_rendered_whole() {
+        let mut renderer = StreamRenderer::new(PLAIN);
+        assert_eq!(renderer.push("```rust\nfn main() {\n\n"), "");
+        assert_eq!(renderer.push("}\n```\n"), "");
+        assert_eq!(renderer.push("\n| a | b |\n|---|---|\n"), "```rust\nfn main() {\n\n}\n```\n");
+        assert_eq!(renderer.push("| 1 | 2 |\n\n"), "");
+        assert_eq!(renderer.push("Between.\n\n"), "\n┌───┬───┐\n│ a │ b │\n├───┼───┤\n│ 1 │ 2 │\n└───┴───┘\n");
+        // A list item after a blank line may still belong to the list above it.
+        assert_eq!(renderer.push("- one\n"), "");
+        assert_eq!(renderer.push("\n- two\n\n"), "");
+        assert_eq!(renderer.push("Done."), "");
+        assert_eq!(renderer.finish(), "\nBetween.\n\n• one\n\n• two\n\nDone.\n");
+    }
+}
@@ -0,0 +1,437 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+// Message catalog for the terminal output (banners, spinners, errors and usage lines). Messages
+// are looked up by key with `msg!`; `{name}` placeholders are filled from the macro's
+// arguments. A key missing from a translation falls back to English.
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UiLanguage {
+    #[serde(rename = "en")]
+    #[value(name = "en")]
+    En,
+    #[serde(rename = "zh-CN")]
+    #[value(name = "zh-CN")]
+    ZhCn,
+}
+
+impl UiLanguage {
+    // The catalog for a locale tag such as "zh-CN" or "en-US"; English when there is none.
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.to_ascii_lowercase().starts_with("zh") { UiLanguage::ZhCn } else { UiLanguage::En }
+    }
+}
+
+static LANGUAGE: OnceLock<UiLanguage> = OnceLock::new();
+
+pub fn init(language: UiLanguage) {
+    let _ = LANGUAGE.set(language);
+}
+
+pub fn text(key: &'static str) -> &'static str {
+    let catalog = match LANGUAGE.get() {
+        Some(UiLanguage::ZhCn) => ZH_CN,
+        _ => EN,
+    };
+    lookup(catalog, key).or_else(|| lookup(EN, key)).unwrap_or(key)
+}
+
+fn lookup(catalog: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, message)| *message)
+}
+
+pub fn fill(template: &str, args: &[(&str, String)]) -> String {
+    let mut message = template.to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+    message
+}
+
+// msg!("key") or msg!("key", name = value, ...), returning the localized String.
+macro_rules! msg {
+    ($key:literal) => {
+        $crate::messages::text($key).to_string()
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::messages::fill($crate::messages::text($key), &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+pub(crate) use msg;
+
+const EN: &[(&str, &str)] = &[
+    // Phase banners
+    ("phase.keywords", "🔍 Phase 1: Generating Search Keywords"),
+    ("phase.search", "🌐 Phase 2: Fetching Search Results (Exa)"),
+    ("phase.answer", "💡 Generating Final Answer (OpenRouter)"),
+    ("phase.answer.2", "💡 Phase 2: Generating Final Answer (OpenRouter)"),
+    ("phase.answer.3", "💡 Phase 3: Generating Final Answer (OpenRouter)"),
+    ("phase.similar", "🌐 Phase 1: Finding Similar Pages (Exa)"),
+    ("phase.fetch.direct", "🌐 Phase 1: Fetching {count} Pages (direct)"),
+    ("phase.fetch.exa", "🌐 Phase 1: Fetching {count} Pages (Exa /contents)"),
+    // Input
+    ("input.query", "Please input what you want in the next line..."),
+    ("input.query_empty", "Input data is empty. Please provide a query."),
+    ("input.query_language", "Query language: {language}"),
+    ("input.paste_urls", "Paste the URLs to summarize, one per line, then press Ctrl-D..."),
+    ("input.no_urls", "No URLs given. Pass them as arguments, with --file, or on stdin."),
+    ("input.invalid_url", "`{url}` is not a valid URL"),
+    ("input.missing_key", "{name} not found in .env file"),
+    // Keyword phase
+    ("keywords.spinner", "Building search query data (via OpenRouter)..."),
+    ("keywords.spinner_language", "Building search query data in {language} (via OpenRouter)..."),
+    ("keywords.done", "✅ Search keywords generated successfully: {keywords}"),
+    ("keywords.error", "Error generating search keywords: {error}"),
+    ("keywords.usage", "🔑 Keyword Generation Token Usage: Prompt: {prompt}, Completion: {completion}, Total: {total}{details}"),
+    ("search.recency", "🕒 Recency constraint applied: published since {since} (the question says \"{cue}\")"),
+    ("search.hint", "💡 Search hint applied: {hint}"),
+    ("search.hint_recency", "published in the last {days} days"),
+    ("search.hint_domains", "domains: {domains}"),
+    // Search phase
+    ("exa.searching", "Searching with Exa: {queries}"),
+    ("exa.no_results", "❌ Exa found no relevant results."),
+    ("exa.no_results_hint", "Try using more general keywords or check the Exa API."),
+    ("exa.search_done", "✅ Exa search completed."),
+    ("exa.search_error", "Error fetching Exa search results: {error}"),
+    ("exa.finding_similar", "Finding pages similar to {url} (via Exa)"),
+    ("exa.no_similar", "❌ Exa found no similar pages."),
+    ("exa.similar_done", "✅ Exa found similar pages."),
+    ("exa.similar_error", "Error fetching similar pages from Exa: {error}"),
+    ("exa.collapsed", "🧹 Collapsed {count} duplicate results"),
+    ("exa.contents_spinner", "Fetching detailed content (via Exa /contents)..."),
+    ("exa.contents_done", "✅ Successfully fetched detailed content for {count} results."),
+    ("exa.contents_parse_failed", "⚠️ Failed to parse Exa /contents response."),
+    ("exa.contents_parse_error", "Failed to parse Exa /contents JSON response: {error}"),
+    ("exa.contents_status_failed", "❌ Exa /contents API request failed (status)."),
+    ("exa.contents_status_error", "Exa /contents API request failed (status): {error}"),
+    ("exa.contents_send_failed", "❌ Failed to send Exa /contents API request."),
+    ("exa.contents_send_error", "Failed to send request to Exa /contents API: {error}"),
+    ("exa.contents_error", "Error fetching page contents: {error}"),
+    ("exa.extracting", "Extracting main content from search results..."),
+    ("exa.extracting_result", "Processing result {index}/{total}..."),
+    ("exa.extracted", "✅ Main content extracted from search results."),
+    ("exa.returned", "Exa returned {count} results (request {request}, search type {search_type})"),
+    ("exa.returned_similar", "Exa returned {count} similar pages (request {request})"),
+    ("exa.contents_request", "Exa /contents request {request}"),
+    ("exa.contents_unavailable", "Exa /contents could not fetch {id}: {reason}"),
+    ("exa.cost", "Exa {endpoint} cost: ${total} (search ${search}, contents ${contents})"),
+    // Result listings
+    ("results.heading", "Summary of relevant web search results:"),
+    ("results.result", "🔍 Result"),
+    ("results.title", "Title"),
+    ("results.url", "URL"),
+    ("results.published", "Published"),
+    ("results.fetched", "Fetched"),
+    ("results.fetched_on", "fetched {date}"),
+    ("results.also_at", "Also at"),
+    ("results.score", "Score"),
+    ("results.similarity", "Similarity"),
+    ("results.similarity_inline", "similarity"),
+    ("results.summary", "Summary"),
+    ("results.summary_search", "Summary (from initial search)"),
+    ("results.summary_direct", "Summary (fetched directly)"),
+    ("results.summary_corpus", "Summary (local document)"),
+    ("results.summary_kb", "Summary (knowledge base)"),
+    ("results.no_content", "(No text content available)"),
+    ("results.empty", "(Content is empty or not fetched after cleaning)"),
+    // Other sources
+    ("kb.answering", "📚 Answering from {count} pages in the knowledge base (pass --kb-mode augment to search the web too)"),
+    ("kb.found", "📚 Found {count} matching pages in the knowledge base"),
+    ("kb.searching", "📚 Searching {count} saved pages"),
+    ("kb.empty", "The knowledge base is empty. Save pages by searching with --kb-mode store, augment or prefer."),
+    ("kb.no_match", "No saved pages match with similarity ≥ {threshold}."),
+    ("kb.saving", "Saving {count} pages to the knowledge base..."),
+    ("kb.save_failed", "❌ Could not save to the knowledge base."),
+    ("kb.saved", "✅ Saved {count} pages to the knowledge base."),
+    ("kb.skipped", "⚠️ Skipping the knowledge base: {error}"),
+    ("kb.save_error", "⚠️ Could not save to the knowledge base: {error}"),
+    ("fetch.spinner", "Fetching {count} pages directly..."),
+    ("fetch.no_text", "No readable text found at {url}"),
+    ("fetch.failed", "Could not fetch {url}: {error}"),
+    ("fetch.done", "✅ Fetched {count} pages directly."),
+    ("fetch.partial", "⚠️ Fetched {count} of {total} pages directly."),
+    ("fetch.robots_failed", "Could not read {origin}/robots.txt: {error}"),
+    ("fetch.robots_status", "{origin}/robots.txt returned {status}"),
+    ("fetch.truncated", "Page cut off at the {limit} byte limit"),
+    ("corpus.found", "📚 Found {count} matching local documents"),
+    ("corpus.no_dir", "No directory to index. Pass one or set `path` in the [corpus] config section."),
+    ("corpus.no_index", "No corpus index to update; run `index build` first"),
+    ("corpus.building", "📚 Building the corpus index for {dir}"),
+    ("corpus.updating", "📚 Updating the corpus index for {dir}"),
+    ("corpus.done", "✅ {added} added, {changed} changed, {unchanged} unchanged, {removed} removed, {skipped} skipped. Index saved to {path}"),
+    ("corpus.indexing", "Indexing {count} files..."),
+    ("corpus.indexing_file", "Indexing file {index}/{total}..."),
+    ("corpus.indexed", "✅ Indexed {count} documents ({passages} passages)."),
+    ("corpus.skipped", "⚠️ Skipping the local corpus: {error}"),
+    ("corpus.matches", "Local corpus {dir}: {count} matching documents"),
+    ("corpus.too_large", "Skipping {path}: larger than {limit} bytes"),
+    ("corpus.file_skipped", "⚠️ Skipping {path}: {error}"),
+    ("summarize.no_content", "⚠️ No content could be fetched for {url}"),
+    ("summarize.no_content_any", "No content could be fetched for any of the given URLs."),
+    ("rerank.failed", "⚠️ Reranking failed, keeping the search order: {error}"),
+    ("rerank.spinner", "Reranking {count} results by semantic similarity..."),
+    ("rerank.spinner_failed", "❌ Reranking failed."),
+    ("rerank.done", "✅ Reranked results: kept {count} of {total} (similarity ≥ {threshold})."),
+    ("embeddings.done", "Embedded {count} inputs with {model} ({tokens} tokens)"),
+    // Answer phase
+    ("answer.context_tokens", "Search context: ~{tokens} tokens"),
+    ("answer.spinner", "Generating final answer using combined information (via OpenRouter)..."),
+    ("answer.receiving", "Receiving final answer from OpenRouter..."),
+    ("answer.received", "✅ Final answer received successfully:"),
+    ("answer.streaming", "✅ Final answer:"),
+    ("answer.thinking", "💭 Thinking..."),
+    ("answer.thinking_summary", "Thinking"),
+    ("answer.error", "Error generating final answer: {error}"),
+    ("answer.usage", "💬 Final Answer Token Usage: Prompt: {prompt}, Completion: {completion}, Total: {total}{details}"),
+    ("answer.request_failed", "❌ {request} failed"),
+    ("stream.bad_chunk", "Warning: Failed to parse stream data chunk from {request}: {error}. Chunk: '{chunk}'"),
+    ("follow_ups.spinner", "Suggesting follow-up questions (via OpenRouter)..."),
+    ("follow_ups.done", "✅ {count} follow-up questions suggested."),
+    ("follow_ups.heading", "Suggested follow-up questions"),
+    ("critique.spinner", "Reviewing the answer against the sources (via OpenRouter)..."),
+    ("critique.done", "✅ Answer reviewed."),
+    ("critique.heading", "Critique"),
+    // Agent mode
+    ("phase.agent", "🤖 Researching with Search Tools (OpenRouter + Exa)"),
+    ("agent.step", "Step {step}/{max}"),
+    ("agent.spinner", "Waiting for the model to search or answer (via OpenRouter)..."),
+    ("agent.tool_calls", "🛠️ {count} tool calls"),
+    ("agent.answered", "✅ The model answered"),
+    ("agent.notes", "    Model notes: {text}"),
+    ("agent.call", "  → {tool}({arguments})"),
+    ("agent.result", "    ← {count} new pages, {seen} already seen"),
+    ("agent.error", "    ← ⚠️ {error}"),
+    ("agent.step_limit", "⚠️ Step limit of {steps} reached; asking for the answer from what was found"),
+    ("agent.no_answer", "The agent stopped after {steps} steps without an answer"),
+    ("agent.no_tools", "⚠️ {model} does not list tool support; the agent may not work with it"),
+    ("agent.sources", "📚 {count} pages consulted:"),
+    // Structured output
+    ("structured.spinner", "Generating the structured answer (via OpenRouter)..."),
+    ("structured.received", "✅ Structured answer received; checking it against the schema"),
+    ("structured.valid", "✅ The answer matches the schema (attempt {attempts})"),
+    ("structured.invalid", "⚠️ Attempt {attempt}: {count} schema problems, asking for a fix. First: {first}"),
+    ("structured.failed", "No answer matching the schema after {attempts} attempts. Last problems: {errors}"),
+    ("structured.json_mode", "⚠️ {model} does not support JSON Schema outputs; asking for plain JSON and validating it here"),
+    ("structured.prompt_only", "⚠️ {model} supports no response format; relying on the prompt and validating the reply here"),
+    ("structured.extras_skipped", "⚠️ --critique and --follow-ups are skipped for structured answers, to keep stdout a single JSON document"),
+    ("structured.with_agent", "Structured answers (--schema) can't be combined with agent mode (--agent)"),
+    // Cost
+    ("cost.run", "💰 Run cost: {total} (OpenRouter {openrouter} for {tokens} tokens, Exa {exa})"),
+    ("cost.unpriced", ", {count} calls with unknown prices"),
+    ("cost.today", " · Today: {total} over {runs} runs"),
+    ("cost.prices_cache_failed", "Could not cache model prices: {error}"),
+    ("cost.prices_cached", "Could not fetch model prices, using cached ones: {error}"),
+    ("cost.prices_built_in", "Could not fetch model prices, using built-in ones: {error}"),
+    ("cost.ledger_read_failed", "⚠️ Could not read the spend ledger: {error}"),
+    ("cost.ledger_write_failed", "⚠️ Could not update the spend ledger: {error}"),
+    ("usage.generation", "Generation {id}: {provider} / {model}, first token after {latency} ms, {time} ms in total; native tokens: {prompt} prompt ({cached} cached), {completion} completion ({reasoning} reasoning)"),
+    ("usage.reasoning", ", Reasoning: {tokens}"),
+    ("usage.generation_id", "Generation {id}: {cached} cached prompt tokens, {reasoning} reasoning tokens"),
+    ("usage.stats_failed", "Could not fetch generation stats for {id}: {error}"),
+    ("generation.unsupported", "⚠️ {model} does not support `{name}`; leaving it out"),
+    ("generation.max_tokens_capped", "⚠️ max_tokens {max_tokens} is above the {limit} that {model} allows; using {limit}"),
+    ("provider.preferences", "Provider routing: {preferences}"),
+    // Budgets
+    ("budget.run_tokens", "run token budget ({left} of {limit} tokens left)"),
+    ("budget.day_tokens", "daily token budget ({left} of {limit} tokens left)"),
+    ("budget.run_usd", "run spending budget ({left} of {limit} left)"),
+    ("budget.day_usd", "daily spending budget ({left} of {limit} left)"),
+    ("budget.unknown_price", "unknown price"),
+    ("budget.abort", "{request} needs ~{tokens} tokens (~{cost}), more than the {limit} allows. Raise the limit in the [budget] config section or with --max-run-tokens / --max-run-usd / --max-day-tokens / --max-day-usd, or pass --on-budget degrade"),
+    ("budget.abort_degraded", "{request} needs ~{tokens} tokens (~{cost}), more than the {limit} allows, even after degrading. Raise the limit in the [budget] config section or with --max-run-tokens / --max-run-usd / --max-day-tokens / --max-day-usd"),
+    ("budget.spent", "{request} not sent: the {limit} is used up"),
+    ("budget.degraded_model", "⚠️ {request} would exceed the {limit}; using {model} instead"),
+    ("budget.degrade_same_model", "⚠️ {request} already uses the degrade model {model}, so it can't be degraded further. Set `degrade_model` in the [budget] config section or pass --degrade-model to switch to a cheaper one"),
+    ("budget.degraded_sources", "⚠️ Answering from the top {count} of {total} sources to stay within the budget"),
+    // `prompts` subcommand
+    ("prompts.overrides_dir", "Overrides are read from {dir}"),
+    ("prompts.personas", "Personas (--persona)"),
+    ("prompts.no_dir", "No prompts directory. Set `dir` in the [prompts] config section."),
+    ("prompts.copied", "📝 Copied the built-in `{name}` prompt to {path}"),
+    ("prompts.saved", "✅ Prompt `{name}` saved to {path}"),
+    ("prompts.built_in", "built-in"),
+    ("prompts.overridden", "overridden by {path}"),
+];
+
+const ZH_CN: &[(&str, &str)] = &[
+    ("phase.keywords", "🔍 阶段 1：生成搜索关键词"),
+    ("phase.search", "🌐 阶段 2：获取搜索结果（Exa）"),
+    ("phase.answer", "💡 生成最终回答（OpenRouter）"),
+    ("phase.answer.2", "💡 阶段 2：生成最终回答（OpenRouter）"),
+    ("phase.answer.3", "💡 阶段 3：生成最终回答（OpenRouter）"),
+    ("phase.similar", "🌐 阶段 1：查找相似页面（Exa）"),
+    ("phase.fetch.direct", "🌐 阶段 1：直接抓取 {count} 个页面"),
+    ("phase.fetch.exa", "🌐 阶段 1：获取 {count} 个页面（Exa /contents）"),
+    ("input.query", "请在下一行输入你的问题……"),
+    ("input.query_empty", "输入为空，请提供一个问题。"),
+    ("input.query_language", "问题语言：{language}"),
+    ("input.paste_urls", "粘贴要总结的 URL，每行一个，然后按 Ctrl-D……"),
+    ("input.no_urls", "未提供 URL。请通过参数、--file 或标准输入传入。"),
+    ("input.invalid_url", "`{url}` 不是有效的 URL"),
+    ("input.missing_key", "在 .env 文件中未找到 {name}"),
+    ("keywords.spinner", "正在生成搜索查询数据（OpenRouter）……"),
+    ("keywords.spinner_language", "正在生成 {language} 搜索查询数据（OpenRouter）……"),
+    ("keywords.done", "✅ 搜索关键词生成成功：{keywords}"),
+    ("keywords.error", "生成搜索关键词时出错：{error}"),
+    ("keywords.usage", "🔑 关键词生成 Token 用量：提示 {prompt}，补全 {completion}，合计 {total}{details}"),
+    ("search.recency", "🕒 已应用时间限制：{since} 之后发布（问题中提到“{cue}”）"),
+    ("search.hint", "💡 已应用搜索提示：{hint}"),
+    ("search.hint_recency", "最近 {days} 天内发布"),
+    ("search.hint_domains", "限定域名：{domains}"),
+    ("exa.searching", "正在使用 Exa 搜索：{queries}"),
+    ("exa.no_results", "❌ Exa 未找到相关结果。"),
+    ("exa.no_results_hint", "请尝试更宽泛的关键词，或检查 Exa API。"),
+    ("exa.search_done", "✅ Exa 搜索完成。"),
+    ("exa.search_error", "获取 Exa 搜索结果时出错：{error}"),
+    ("exa.finding_similar", "正在查找与 {url} 相似的页面（Exa）"),
+    ("exa.no_similar", "❌ Exa 未找到相似页面。"),
+    ("exa.similar_done", "✅ Exa 已找到相似页面。"),
+    ("exa.similar_error", "从 Exa 获取相似页面时出错：{error}"),
+    ("exa.collapsed", "🧹 合并了 {count} 个重复结果"),
+    ("exa.contents_spinner", "正在获取详细内容（Exa /contents）……"),
+    ("exa.contents_done", "✅ 已获取 {count} 个结果的详细内容。"),
+    ("exa.contents_parse_failed", "⚠️ 无法解析 Exa /contents 响应。"),
+    ("exa.contents_parse_error", "无法解析 Exa /contents 的 JSON 响应：{error}"),
+    ("exa.contents_status_failed", "❌ Exa /contents API 请求失败（状态码）。"),
+    ("exa.contents_status_error", "Exa /contents API 请求失败（状态码）：{error}"),
+    ("exa.contents_send_failed", "❌ 无法发送 Exa /contents API 请求。"),
+    ("exa.contents_send_error", "向 Exa /contents API 发送请求失败：{error}"),
+    ("exa.contents_error", "获取页面内容时出错：{error}"),
+    ("exa.extracting", "正在从搜索结果中提取正文……"),
+    ("exa.extracting_result", "正在处理第 {index}/{total} 个结果……"),
+    ("exa.extracted", "✅ 已从搜索结果中提取正文。"),
+    ("exa.returned", "Exa 返回了 {count} 个结果（请求 {request}，搜索类型 {search_type}）"),
+    ("exa.returned_similar", "Exa 返回了 {count} 个相似页面（请求 {request}）"),
+    ("exa.contents_request", "Exa /contents 请求 {request}"),
+    ("exa.contents_unavailable", "Exa /contents 无法获取 {id}：{reason}"),
+    ("exa.cost", "Exa {endpoint} 费用：${total}（搜索 ${search}，内容 ${contents}）"),
+    ("results.heading", "相关网页搜索结果摘要："),
+    ("results.result", "🔍 结果"),
+    ("results.title", "标题"),
+    ("results.url", "网址"),
+    ("results.published", "发布时间"),
+    ("results.fetched", "抓取时间"),
+    ("results.fetched_on", "抓取于 {date}"),
+    ("results.also_at", "其他地址"),
+    ("results.score", "得分"),
+    ("results.similarity", "相似度"),
+    ("results.similarity_inline", "相似度"),
+    ("results.summary", "摘要"),
+    ("results.summary_search", "摘要（来自初始搜索）"),
+    ("results.summary_direct", "摘要（直接抓取）"),
+    ("results.summary_corpus", "摘要（本地文档）"),
+    ("results.summary_kb", "摘要（知识库）"),
+    ("results.no_content", "（没有可用的文本内容）"),
+    ("results.empty", "（内容为空，或清理后未获取到内容）"),
+    ("kb.answering", "📚 使用知识库中的 {count} 个页面作答（传入 --kb-mode augment 可同时搜索网络）"),
+    ("kb.found", "📚 在知识库中找到 {count} 个匹配页面"),
+    ("kb.searching", "📚 正在搜索 {count} 个已保存页面"),
+    ("kb.empty", "知识库为空。使用 --kb-mode store、augment 或 prefer 进行搜索即可保存页面。"),
+    ("kb.no_match", "没有相似度 ≥ {threshold} 的已保存页面。"),
+    ("kb.saving", "正在将 {count} 个页面保存到知识库……"),
+    ("kb.save_failed", "❌ 无法保存到知识库。"),
+    ("kb.saved", "✅ 已将 {count} 个页面保存到知识库。"),
+    ("kb.skipped", "⚠️ 跳过知识库：{error}"),
+    ("kb.save_error", "⚠️ 无法保存到知识库：{error}"),
+    ("fetch.spinner", "正在直接抓取 {count} 个页面……"),
+    ("fetch.no_text", "在 {url} 未找到可读文本"),
+    ("fetch.failed", "无法抓取 {url}：{error}"),
+    ("fetch.done", "✅ 已直接抓取 {count} 个页面。"),
+    ("fetch.partial", "⚠️ 已直接抓取 {count} / {total} 个页面。"),
+    ("fetch.robots_failed", "无法读取 {origin}/robots.txt：{error}"),
+    ("fetch.robots_status", "{origin}/robots.txt 返回 {status}"),
+    ("fetch.truncated", "页面在 {limit} 字节上限处被截断"),
+    ("corpus.found", "📚 找到 {count} 个匹配的本地文档"),
+    ("corpus.no_dir", "没有要索引的目录。请传入目录，或在 [corpus] 配置中设置 `path`。"),
+    ("corpus.no_index", "没有可更新的语料索引，请先运行 `index build`"),
+    ("corpus.building", "📚 正在为 {dir} 构建语料索引"),
+    ("corpus.updating", "📚 正在更新 {dir} 的语料索引"),
+    ("corpus.done", "✅ 新增 {added}，变更 {changed}，未变 {unchanged}，删除 {removed}，跳过 {skipped}。索引已保存到 {path}"),
+    ("corpus.indexing", "正在索引 {count} 个文件……"),
+    ("corpus.indexing_file", "正在索引第 {index}/{total} 个文件……"),
+    ("corpus.indexed", "✅ 已索引 {count} 个文档（{passages} 个段落）。"),
+    ("corpus.skipped", "⚠️ 跳过本地语料：{error}"),
+    ("corpus.matches", "本地语料 {dir}：{count} 个匹配文档"),
+    ("corpus.too_large", "跳过 {path}：超过 {limit} 字节"),
+    ("corpus.file_skipped", "⚠️ 跳过 {path}：{error}"),
+    ("summarize.no_content", "⚠️ 无法获取 {url} 的内容"),
+    ("summarize.no_content_any", "所有给定的 URL 都无法获取内容。"),
+    ("rerank.failed", "⚠️ 重排序失败，保留搜索顺序：{error}"),
+    ("rerank.spinner", "正在按语义相似度对 {count} 个结果重排序……"),
+    ("rerank.spinner_failed", "❌ 重排序失败。"),
+    ("rerank.done", "✅ 重排序完成：保留 {count} / {total} 个结果（相似度 ≥ {threshold}）。"),
+    ("embeddings.done", "已用 {model} 嵌入 {count} 条输入（{tokens} 个 token）"),
+    ("answer.context_tokens", "搜索上下文：约 {tokens} 个 token"),
+    ("answer.spinner", "正在综合信息生成最终回答（OpenRouter）……"),
+    ("answer.receiving", "正在接收 OpenRouter 的最终回答……"),
+    ("answer.received", "✅ 已成功接收最终回答："),
+    ("answer.streaming", "✅ 最终回答："),
+    ("answer.thinking", "💭 思考中……"),
+    ("answer.thinking_summary", "思考过程"),
+    ("answer.error", "生成最终回答时出错：{error}"),
+    ("answer.usage", "💬 最终回答 Token 用量：提示 {prompt}，补全 {completion}，合计 {total}{details}"),
+    ("answer.request_failed", "❌ {request} 失败"),
+    ("stream.bad_chunk", "警告：无法解析来自 {request} 的流式数据块：{error}。数据块：'{chunk}'"),
+    ("follow_ups.spinner", "正在生成后续问题建议（OpenRouter）……"),
+    ("follow_ups.done", "✅ 已生成 {count} 个后续问题。"),
+    ("follow_ups.heading", "推荐的后续问题"),
+    ("critique.spinner", "正在对照来源评审回答（OpenRouter）……"),
+    ("critique.done", "✅ 回答评审完成。"),
+    ("critique.heading", "评审意见"),
+    ("phase.agent", "🤖 使用搜索工具研究问题（OpenRouter + Exa）"),
+    ("agent.step", "第 {step}/{max} 步"),
+    ("agent.spinner", "正在等待模型搜索或作答（OpenRouter）……"),
+    ("agent.tool_calls", "🛠️ {count} 次工具调用"),
+    ("agent.answered", "✅ 模型已作答"),
+    ("agent.notes", "    模型说明：{text}"),
+    ("agent.call", "  → {tool}({arguments})"),
+    ("agent.result", "    ← 新页面 {count} 个，已见过 {seen} 个"),
+    ("agent.error", "    ← ⚠️ {error}"),
+    ("agent.step_limit", "⚠️ 已达到 {steps} 步的上限，要求模型根据已有结果作答"),
+    ("agent.no_answer", "智能体在 {steps} 步后停止，没有给出回答"),
+    ("agent.no_tools", "⚠️ {model} 未声明支持工具调用，智能体模式可能无法使用"),
+    ("agent.sources", "📚 参考了 {count} 个页面："),
+    ("structured.spinner", "正在生成结构化回答（OpenRouter）……"),
+    ("structured.received", "✅ 已收到结构化回答，正在按 Schema 校验"),
+    ("structured.valid", "✅ 回答符合 Schema（第 {attempts} 次尝试）"),
+    ("structured.invalid", "⚠️ 第 {attempt} 次尝试：有 {count} 处不符合 Schema，正在要求修正。首个问题：{first}"),
+    ("structured.failed", "尝试 {attempts} 次后仍没有符合 Schema 的回答。最后的问题：{errors}"),
+    ("structured.json_mode", "⚠️ {model} 不支持 JSON Schema 输出，改为请求普通 JSON 并在本地校验"),
+    ("structured.prompt_only", "⚠️ {model} 不支持任何响应格式，仅依靠提示词并在本地校验回答"),
+    ("structured.extras_skipped", "⚠️ 结构化回答会跳过 --critique 和 --follow-ups，以保证标准输出只有一个 JSON 文档"),
+    ("structured.with_agent", "结构化回答（--schema）不能与智能体模式（--agent）同时使用"),
+    ("cost.run", "💰 本次花费：{total}（OpenRouter {openrouter}，{tokens} 个 token；Exa {exa}）"),
+    ("cost.unpriced", "，{count} 次调用价格未知"),
+    ("cost.today", " · 今日累计：{total}，共 {runs} 次运行"),
+    ("cost.prices_cache_failed", "无法缓存模型价格：{error}"),
+    ("cost.prices_cached", "无法获取模型价格，改用缓存的价格：{error}"),
+    ("cost.prices_built_in", "无法获取模型价格，改用内置价格：{error}"),
+    ("cost.ledger_read_failed", "⚠️ 无法读取花费账本：{error}"),
+    ("cost.ledger_write_failed", "⚠️ 无法更新花费账本：{error}"),
+    ("usage.generation", "生成 {id}：{provider} / {model}，首个 token 用时 {latency} 毫秒，总计 {time} 毫秒；原生 token：提示 {prompt}（缓存 {cached}），补全 {completion}（推理 {reasoning}）"),
+    ("usage.reasoning", "，推理 {tokens}"),
+    ("usage.generation_id", "生成 {id}：缓存的提示 token {cached} 个，推理 token {reasoning} 个"),
+    ("usage.stats_failed", "无法获取生成 {id} 的统计信息：{error}"),
+    ("generation.unsupported", "⚠️ {model} 不支持 `{name}`，已忽略该参数"),
+    ("generation.max_tokens_capped", "⚠️ max_tokens {max_tokens} 超过 {model} 允许的 {limit}，改用 {limit}"),
+    ("provider.preferences", "服务商路由：{preferences}"),
+    ("budget.run_tokens", "单次 token 预算（剩余 {left} / {limit} 个 token）"),
+    ("budget.day_tokens", "每日 token 预算（剩余 {left} / {limit} 个 token）"),
+    ("budget.run_usd", "单次花费预算（剩余 {left} / {limit}）"),
+    ("budget.day_usd", "每日花费预算（剩余 {left} / {limit}）"),
+    ("budget.unknown_price", "价格未知"),
+    ("budget.abort", "{request} 预计需要约 {tokens} 个 token（约 {cost}），超出了{limit}。请在 [budget] 配置中或用 --max-run-tokens / --max-run-usd / --max-day-tokens / --max-day-usd 提高上限，或传入 --on-budget degrade"),
+    ("budget.abort_degraded", "{request} 预计需要约 {tokens} 个 token（约 {cost}），降级后仍超出了{limit}。请在 [budget] 配置中或用 --max-run-tokens / --max-run-usd / --max-day-tokens / --max-day-usd 提高上限"),
+    ("budget.spent", "{request} 未发送：{limit}已用完"),
+    ("budget.degraded_model", "⚠️ {request} 将超出{limit}，改用 {model}"),
+    ("budget.degrade_same_model", "⚠️ {request} 已在使用降级模型 {model}，无法再降级。请在 [budget] 配置中设置 `degrade_model`，或传入 --degrade-model 指定更便宜的模型"),
+    ("budget.degraded_sources", "⚠️ 为控制在预算内，仅使用前 {count} 个来源（共 {total} 个）生成回答"),
+    ("prompts.overrides_dir", "自定义模板目录：{dir}"),
+    ("prompts.personas", "角色预设（--persona）"),
+    ("prompts.no_dir", "没有模板目录。请在 [prompts] 配置中设置 `dir`。"),
+    ("prompts.copied", "📝 已将内置的 `{name}` 模板复制到 {path}"),
+    ("prompts.saved", "✅ 模板 `{name}` 已保存到 {path}"),
+    ("prompts.built_in", "内置"),
+    ("prompts.overridden", "已被 {path} 覆盖"),
+];
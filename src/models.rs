@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::generation::GenerationParams;
+use crate::provider::ProviderPreferences;
+
 // OpenRouter related structs
 #[derive(Serialize)]
 pub struct OpenRouterRequest<'a> {
@@ -7,12 +10,77 @@ pub struct OpenRouterRequest<'a> {
     pub messages: Vec<Message<'a>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>, // Added for streaming
+    pub usage: UsageRequest, // asks OpenRouter to send usage in the last chunk
+    #[serde(flatten)]
+    pub params: GenerationParams,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<&'a ProviderPreferences>, // routing and data policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<&'a [ToolDefinition]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<&'a str>, // "auto" or "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat<'a>>,
+}
+
+// Constrains the reply to JSON: any JSON object, or one matching a schema.
+#[derive(Serialize, Debug, Clone)]
+pub struct ResponseFormat<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str, // "json_schema" or "json_object"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<JsonSchemaFormat<'a>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
+pub struct JsonSchemaFormat<'a> {
+    pub name: &'a str,
+    pub strict: bool,
+    pub schema: &'a serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct UsageRequest {
+    pub include: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
 pub struct Message<'a> {
     pub role: &'a str,
     pub content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<&'a [ToolCall]>, // on assistant messages that called tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<&'a str>, // on the `tool` message answering a call
+}
+
+// Function calling: the tools offered with a request, and the calls the model makes.
+#[derive(Serialize, Debug, Clone)]
+pub struct ToolDefinition {
+    #[serde(rename = "type")]
+    pub kind: &'static str, // always "function"
+    pub function: FunctionDefinition,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FunctionDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: serde_json::Value, // JSON Schema of the arguments
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String, // JSON text
 }
 
 #[derive(Deserialize, Debug, Clone, Default)] // Added Default
@@ -20,18 +88,107 @@ pub struct UsageInfo {
     pub prompt_tokens: u32,
     pub completion_tokens: Option<u32>, // Made optional as it might not always be present initially in stream
     pub total_tokens: u32,
+    #[serde(default)]
+    pub cost: Option<f64>, // USD; filled in from the model's price when OpenRouter doesn't report it
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    #[serde(default)]
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+    #[serde(skip)]
+    pub generation_id: Option<String>,
+    #[serde(skip)]
+    pub stats: Option<GenerationStats>, // when fetched from the generation-stats endpoint
+}
+
+impl UsageInfo {
+    // Adds another call's usage, for totals over several requests.
+    pub fn add(&mut self, other: &UsageInfo) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens = Some(self.completion_tokens.unwrap_or(0) + other.completion_tokens.unwrap_or(0));
+        self.total_tokens += other.total_tokens;
+        self.cost = match (self.cost, other.cost) {
+            (Some(cost), Some(other_cost)) => Some(cost + other_cost),
+            (cost, other_cost) => cost.or(other_cost),
+        };
+    }
+
+    pub fn cached_tokens(&self) -> Option<u32> {
+        self.prompt_tokens_details.as_ref().and_then(|details| details.cached_tokens)
+    }
+
+    pub fn reasoning_tokens(&self) -> Option<u32> {
+        self.completion_tokens_details.as_ref().and_then(|details| details.reasoning_tokens)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PromptTokensDetails {
+    pub cached_tokens: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CompletionTokensDetails {
+    pub reasoning_tokens: Option<u32>,
+}
+
+// GET /generation?id=..., available shortly after a request finishes.
+#[derive(Deserialize, Debug)]
+pub struct GenerationStatsResponse {
+    pub data: GenerationStats,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GenerationStats {
+    pub model: Option<String>,
+    pub provider_name: Option<String>,
+    pub total_cost: Option<f64>,
+    pub latency: Option<u64>, // ms until the first token
+    pub generation_time: Option<u64>, // ms
+    pub native_tokens_prompt: Option<u32>,
+    pub native_tokens_completion: Option<u32>,
+    pub native_tokens_reasoning: Option<u32>,
+    pub native_tokens_cached: Option<u32>,
+}
+
+// GET /models, used for per-token prices.
+#[derive(Deserialize, Debug)]
+pub struct OpenRouterModelsResponse {
+    pub data: Vec<OpenRouterModel>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenRouterModel {
+    pub id: String,
+    pub pricing: OpenRouterModelPricing,
+    pub context_length: Option<u64>,
+    pub top_provider: Option<OpenRouterTopProvider>,
+    #[serde(default)]
+    pub supported_parameters: Vec<String>, // request parameters the model accepts, e.g. "temperature"
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenRouterTopProvider {
+    pub max_completion_tokens: Option<u32>,
+}
+
+// USD per token, as decimal strings.
+#[derive(Deserialize, Debug)]
+pub struct OpenRouterModelPricing {
+    pub prompt: String,
+    pub completion: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct OpenRouterError {
-    _message: String,
+    pub message: String,
 }
 
 // Structs for handling streaming responses
 #[derive(Deserialize, Debug)]
 pub struct OpenRouterStreamResponse {
-    pub _id: Option<String>,
+    pub id: Option<String>, // generation id, the same in every chunk
     pub _model: Option<String>,
+    #[serde(default)]
     pub choices: Vec<OpenRouterStreamChoice>,
     pub usage: Option<UsageInfo>, // To capture usage at the end of the stream
     pub error: Option<OpenRouterError>,
@@ -39,6 +196,7 @@ pub struct OpenRouterStreamResponse {
 
 #[derive(Deserialize, Debug)]
 pub struct OpenRouterStreamChoice {
+    #[allow(dead_code)]
     pub index: u32,
     pub delta: OpenRouterStreamDelta,
     pub finish_reason: Option<String>,
@@ -47,48 +205,381 @@ pub struct OpenRouterStreamChoice {
 #[derive(Deserialize, Debug)]
 pub struct OpenRouterStreamDelta {
     pub content: Option<String>,
+    pub reasoning: Option<String>, // thinking of reasoning models, streamed before the content
+    pub tool_calls: Option<Vec<ToolCallDelta>>, // some providers send null instead of leaving it out
     // Potentially other fields like 'role' if the role can change mid-stream
 }
 
+// A piece of a tool call: the id and name come first, the arguments in fragments.
+#[derive(Deserialize, Debug)]
+pub struct ToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
 // Exa related structs
+// Exa's JSON is camelCase on both requests and responses; every struct below renames accordingly.
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ExaSearchRequest<'a> {
     pub query: &'a str,
     pub num_results: usize,
     pub use_autoprompt: bool,
-    pub text: bool,
+    #[serde(flatten)]
+    pub contents: &'a ExaContentOptions,
+    #[serde(flatten)]
+    pub options: &'a ExaSearchOptions,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaFindSimilarRequest<'a> {
+    pub url: &'a str,
+    pub num_results: usize,
+    pub exclude_source_domain: bool,
+    #[serde(flatten)]
+    pub contents: &'a ExaContentOptions,
+    #[serde(flatten)]
+    pub options: &'a ExaSearchOptions,
+}
+
+// Optional search filters, set from the config file and CLI flags.
+// Field names are snake_case in the config file and camelCase on the wire.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all(serialize = "camelCase"))]
+pub struct ExaSearchOptions {
+    #[serde(rename(serialize = "type"), skip_serializing_if = "Option::is_none")]
+    pub search_type: Option<ExaSearchType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<ExaCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_published_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_published_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_domains: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_domains: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_text: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_text: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExaSearchType {
+    Auto,
+    Neural,
+    Keyword,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExaCategory {
+    Company,
+    #[serde(rename = "research paper")]
+    ResearchPaper,
+    News,
+    Pdf,
+    Github,
+    Tweet,
+    #[serde(rename = "personal site")]
+    PersonalSite,
+    #[serde(rename = "linkedin profile")]
+    LinkedinProfile,
+    #[serde(rename = "financial report")]
+    FinancialReport,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct ExaSearchResponse {
+    pub request_id: Option<String>,
+    pub resolved_search_type: Option<String>,
     pub results: Vec<ExaSearchResult>,
+    pub cost_dollars: Option<ExaCostDollars>,
 }
 
 #[derive(Deserialize, Debug, Clone)] // Added Clone here as it might be useful later
+#[serde(rename_all = "camelCase")]
 pub struct ExaSearchResult {
-    pub title: String,
+    pub id: String,
     pub url: String,
-    pub id: Option<String>,
+    pub title: Option<String>, // Exa sends null for pages without a title
+    pub score: Option<f64>,
+    pub published_date: Option<String>,
+    pub author: Option<String>,
+    #[allow(dead_code)] // part of the schema, not displayed yet
+    pub image: Option<String>,
+    #[allow(dead_code)]
+    pub favicon: Option<String>,
     pub text: Option<String>,
-    pub _score: Option<f64>,
-    pub _published_date: Option<String>,
-    pub _author: Option<String>,
+    #[serde(default)]
+    pub highlights: Vec<String>,
+    #[serde(default)]
+    pub highlight_scores: Vec<f64>,
+    pub summary: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaCostDollars {
+    pub total: f64,
+    #[serde(default)]
+    pub break_down: Vec<ExaCostBreakdown>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExaCostBreakdown {
+    pub search: Option<f64>,
+    pub contents: Option<f64>,
 }
 
 #[derive(Serialize)]
 pub struct ExaContentsRequest<'a> {
     pub ids: Vec<&'a str>,
+    #[serde(flatten)]
+    pub contents: &'a ExaContentOptions,
+}
+
+// Which content modes to return for each result (`text`, `highlights`, `summary`).
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaContentOptions {
+    pub text: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<ExaHighlightsOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ExaSummaryOptions>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaHighlightsOptions {
+    pub query: String,
+    pub num_sentences: u32,
+    pub highlights_per_url: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaSummaryOptions {
+    pub query: String,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct ExaContentResult {
     pub id: String,
-    pub text: String,
-    // pub url: Option<String>,
-    // pub title: Option<String>,
+    #[allow(dead_code)] // results are matched to search hits by id
+    pub url: Option<String>,
+    pub title: Option<String>,
+    pub published_date: Option<String>,
+    pub author: Option<String>,
+    #[allow(dead_code)] // part of the schema, not displayed yet
+    pub image: Option<String>,
+    #[allow(dead_code)]
+    pub favicon: Option<String>,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub highlights: Vec<String>,
+    #[serde(default)]
+    pub highlight_scores: Vec<f64>,
+    pub summary: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct ExaContentsResponse {
+    pub request_id: Option<String>,
     pub results: Vec<ExaContentResult>,
+    #[serde(default)]
+    pub statuses: Vec<ExaContentStatus>,
+    pub cost_dollars: Option<ExaCostDollars>,
+}
+
+// Per-id crawl outcome; failed ids are missing from `results`.
+#[derive(Deserialize, Debug)]
+pub struct ExaContentStatus {
+    pub id: String,
+    pub status: String,
+    pub error: Option<ExaContentError>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExaContentError {
+    pub tag: Option<String>,
+    pub http_status_code: Option<u16>,
+}
+
+// OpenAI-compatible embeddings (OpenAI itself or a local server)
+#[derive(Serialize)]
+pub struct EmbeddingRequest<'a> {
+    pub model: &'a str,
+    pub input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddingResponse {
+    pub data: Vec<EmbeddingData>,
+    pub usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddingData {
+    pub embedding: Vec<f32>,
+    pub index: usize,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmbeddingUsage {
+    pub total_tokens: u32,
+}
+
+// Keyword generation output. The model is asked for JSON so it can also suggest
+// search filters; `recency_days` and `domains` are only hints.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct KeywordSuggestion {
+    pub keywords: String,
+    #[serde(default)]
+    pub recency_days: Option<u32>,
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    // Recorded Exa payloads; see tests/fixtures/exa.
+    const SEARCH_REQUEST: &str = include_str!("../tests/fixtures/exa/search_request.json");
+    const SEARCH_RESPONSE: &str = include_str!("../tests/fixtures/exa/search_response.json");
+    const CONTENTS_REQUEST: &str = include_str!("../tests/fixtures/exa/contents_request.json");
+    const CONTENTS_RESPONSE: &str = include_str!("../tests/fixtures/exa/contents_response.json");
+
+    fn fixture(raw: &str) -> Value {
+        serde_json::from_str(raw).expect("fixture is valid JSON")
+    }
+
+    #[test]
+    fn search_request_uses_exa_field_names() {
+        let options = ExaSearchOptions {
+            search_type: Some(ExaSearchType::Neural),
+            category: Some(ExaCategory::ResearchPaper),
+            start_published_date: Some("2024-01-01T00:00:00.000Z".to_string()),
+            include_domains: vec!["rust-lang.org".to_string()],
+            exclude_text: vec!["sponsored".to_string()],
+            ..Default::default()
+        };
+        let request = ExaSearchRequest {
+            query: "rust 1.80 release notes",
+            num_results: 10,
+            use_autoprompt: false,
+            contents: &ExaContentOptions { text: true, ..Default::default() },
+            options: &options,
+        };
+        assert_eq!(serde_json::to_value(&request).unwrap(), fixture(SEARCH_REQUEST));
+    }
+
+    #[test]
+    fn search_request_carries_content_options() {
+        let contents = ExaContentOptions {
+            text: true,
+            highlights: Some(ExaHighlightsOptions { query: "what changed".to_string(), num_sentences: 3, highlights_per_url: 2 }),
+            summary: Some(ExaSummaryOptions { query: "what changed".to_string() }),
+        };
+        let options = ExaSearchOptions::default();
+        let search = ExaSearchRequest { query: "rust 1.80", num_results: 10, use_autoprompt: false, contents: &contents, options: &options };
+        let similar = ExaFindSimilarRequest { url: "https://blog.rust-lang.org/", num_results: 10, exclude_source_domain: true, contents: &contents, options: &options };
+        for request in [serde_json::to_value(&search).unwrap(), serde_json::to_value(&similar).unwrap()] {
+            assert_eq!(request["text"], true);
+            assert_eq!(request["highlights"], serde_json::json!({ "query": "what changed", "numSentences": 3, "highlightsPerUrl": 2 }));
+            assert_eq!(request["summary"], serde_json::json!({ "query": "what changed" }));
+        }
+    }
+
+    #[test]
+    fn contents_request_uses_exa_field_names() {
+        let contents = ExaContentOptions {
+            text: true,
+            highlights: Some(ExaHighlightsOptions {
+                query: "what changed in rust 1.80".to_string(),
+                num_sentences: 3,
+                highlights_per_url: 2,
+            }),
+            summary: Some(ExaSummaryOptions {
+                query: "what changed in rust 1.80".to_string(),
+            }),
+        };
+        let request = ExaContentsRequest {
+            ids: vec!["https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html"],
+            contents: &contents,
+        };
+        assert_eq!(serde_json::to_value(&request).unwrap(), fixture(CONTENTS_REQUEST));
+    }
+
+    #[test]
+    fn search_response_deserializes_all_fields() {
+        let response: ExaSearchResponse = serde_json::from_str(SEARCH_RESPONSE).unwrap();
+        assert_eq!(response.request_id.as_deref(), Some("b5947044c4b78efa9552a7c89b306d95"));
+        assert_eq!(response.resolved_search_type.as_deref(), Some("neural"));
+        assert_eq!(response.results.len(), 2);
+
+        let first = &response.results[0];
+        assert_eq!(first.id, "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html");
+        assert_eq!(first.title.as_deref(), Some("Announcing Rust 1.80.0 | Rust Blog"));
+        assert_eq!(first.score, Some(0.4271));
+        assert_eq!(first.published_date.as_deref(), Some("2024-07-25T00:00:00.000Z"));
+        assert_eq!(first.author.as_deref(), Some("The Rust Release Team"));
+        assert!(first.image.as_deref().is_some_and(|url| url.ends_with("rust-social-wide.jpg")));
+        assert!(first.favicon.as_deref().is_some_and(|url| url.ends_with("favicon-32x32.png")));
+        assert!(first.text.as_deref().is_some_and(|text| text.contains("LazyLock")));
+
+        let second = &response.results[1];
+        assert_eq!(second.title, None);
+        assert_eq!(second.published_date, None);
+        assert_eq!(second.author, None);
+        assert!(second.highlights.is_empty());
+
+        let cost = response.cost_dollars.expect("costDollars is parsed");
+        assert_eq!(cost.total, 0.005);
+        assert_eq!(cost.break_down[0].search, Some(0.005));
+        assert_eq!(cost.break_down[0].contents, Some(0.0));
+    }
+
+    #[test]
+    fn contents_response_deserializes_highlights_summary_and_statuses() {
+        let response: ExaContentsResponse = serde_json::from_str(CONTENTS_RESPONSE).unwrap();
+        assert_eq!(response.request_id.as_deref(), Some("e492118ccdedcba5088bfc4357a8a125"));
+        assert_eq!(response.results.len(), 1);
+
+        let result = &response.results[0];
+        assert_eq!(result.url.as_deref(), Some("https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html"));
+        assert_eq!(result.published_date.as_deref(), Some("2024-07-25T00:00:00.000Z"));
+        assert_eq!(result.author.as_deref(), Some("The Rust Release Team"));
+        assert!(result.image.is_some() && result.favicon.is_some());
+        assert_eq!(result.highlights.len(), 2);
+        assert_eq!(result.highlight_scores, vec![0.61, 0.74]);
+        assert!(result.summary.as_deref().is_some_and(|s| s.starts_with("Rust 1.80.0")));
+
+        assert_eq!(response.statuses.len(), 2);
+        let failed = &response.statuses[1];
+        assert_eq!(failed.status, "error");
+        let error = failed.error.as_ref().unwrap();
+        assert_eq!(error.tag.as_deref(), Some("CRAWL_NOT_FOUND"));
+        assert_eq!(error.http_status_code, Some(404));
+        assert_eq!(response.cost_dollars.map(|c| c.total), Some(0.003));
+    }
 }
@@ -1,169 +1,383 @@
-use crate::constants::open_router::{API_URL, APPNAME, REFERER, SEARCH_MODEL, SUMMARY_MODEL};
+use crate::constants::open_router::{
+    API_URL, APPNAME, GENERATION_STATS_ATTEMPTS, GENERATION_URL, REFERER, SEARCH_MODEL, SUMMARY_MODEL,
+};
 use crate::models::{
-    Message, OpenRouterRequest, OpenRouterStreamResponse, UsageInfo,
+    GenerationStats, GenerationStatsResponse, KeywordSuggestion, Message, OpenRouterRequest, OpenRouterStreamResponse,
+    ResponseFormat, ToolCall, ToolDefinition, UsageInfo, UsageRequest,
 };
-use crate::utils::create_spinner;
+use crate::budget;
+use crate::cost;
+use crate::generation::{GenerationOptions, GenerationParams};
+use crate::messages::msg;
+use crate::output;
+use crate::prompts::{PromptName, PromptVars, Prompts};
+use crate::provider;
+use crate::reasoning::{self, ThinkingDisplay};
+use crate::utils::{Spinner, create_spinner};
 use anyhow::{Context, Ok, Result};
 use colored::Colorize;
 use futures_util::StreamExt;
 use reqwest::Client;
-use std::io::{Write, stdout};
 use std::time::Duration;
 
 // Helper function to generate search keywords using OpenRouter
 pub async fn generate_search_keywords(
     http_client: &Client,
     openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
     user_query: &str,
-) -> Result<(String, Option<UsageInfo>)> {
-    let keyword_spinner = create_spinner("Building search query data (via OpenRouter)...");
-    let keyword_prompt = format!(
-        "Based on the following user query, generate 3-5 concise search keywords suitable for a web search engine. Return only the keywords, comma-separated. User query: \"{}\"",
-        user_query,
-    );
-
-    let timeout = Duration::new(300, 0);
-    let keyword_request_payload = OpenRouterRequest {
-        model: SEARCH_MODEL, // Or your preferred model for keyword generation
-        stream: Some(true),
-        messages: vec![Message {
-            role: "user",
-            content: &keyword_prompt,
-        }],
+    language: Option<&str>,
+) -> Result<(KeywordSuggestion, Option<UsageInfo>)> {
+    let keyword_prompt = prompts.render(PromptName::Keyword, &PromptVars {
+        language: language.map(str::to_string),
+        ..prompts.vars(user_query)
+    })?;
+    let keyword_spinner = match language {
+        Some(language) => create_spinner(&msg!("keywords.spinner_language", language = language)),
+        None => create_spinner(&msg!("keywords.spinner")),
     };
 
-    let keyword_response_raw = http_client
-        .post(API_URL)
-        .timeout(timeout)
-        .bearer_auth(openrouter_api_key)
-        .header("Content-Type", "application/json")
-        .header("HTTP-Referer", REFERER)
-        .header("X-Title", APPNAME)
-        .json(&keyword_request_payload)
-        .send()
+    // Or your preferred model for keyword generation
+    let model = budget::admit(SEARCH_MODEL, "OpenRouter Keyword Generation", prompts.system(), &keyword_prompt)?;
+    let keyword_response_raw = send_chat(http_client, openrouter_api_key, &model, generation.phase(PromptName::Keyword), prompts.system(), &keyword_prompt)
         .await
         .context("Failed to send request to OpenRouter for keyword generation")?;
 
     // Streaming for keywords might be overkill for display, but useful for consistent API usage and getting token counts.
     // For keywords, we'll collect the full response then return, not printing chunk by chunk.
-    let (keywords_content, usage_info) = handle_openrouter_response(
+    let ChatResponse { content: keywords_content, usage: usage_info, .. } = handle_openrouter_response(
+        http_client,
+        openrouter_api_key,
         keyword_response_raw,
+        &model,
         "OpenRouter Keyword Generation",
         false,
         &keyword_spinner,
     )
     .await?; // Retained original context_msg as it's for internal logging/error handling
 
-    keyword_spinner.finish_with_message(format!(
-        "✅ Search keywords generated successfully: {}",
-        keywords_content
-    ));
-    Ok((keywords_content, usage_info))
+    let suggestion = parse_keyword_suggestion(&keywords_content);
+    keyword_spinner.finish_with_message(msg!("keywords.done", keywords = suggestion.keywords));
+    Ok((suggestion, usage_info))
+}
+
+// Models don't always follow the JSON instruction (or wrap it in a code fence),
+// so fall back to treating the whole reply as the keyword list.
+fn parse_keyword_suggestion(content: &str) -> KeywordSuggestion {
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content,
+    };
+    match serde_json::from_str::<KeywordSuggestion>(json) {
+        Result::Ok(suggestion) if !suggestion.keywords.trim().is_empty() => suggestion,
+        _ => KeywordSuggestion {
+            keywords: content.trim().to_string(),
+            ..Default::default()
+        },
+    }
 }
 
 // Helper function to generate the final answer using OpenRouter
 pub async fn generate_final_answer(
     http_client: &Client,
     openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
     user_query: &str,
     search_results_summary: &str,
 ) -> Result<(String, Option<UsageInfo>)> {
+    let final_prompt = final_answer_prompt(prompts, user_query, search_results_summary)?;
     let final_answer_spinner =
-        create_spinner("Generating final answer using combined information (via OpenRouter)...");
-    let final_prompt = format!(
-        "Based on your existing knowledge and the following web search results, please provide a comprehensive answer to the user's original query. \n\nUser Query: \"{}\"\n\nWeb Search Results:\n{}\n\nYour Answer:",
-        user_query, search_results_summary
-    );
+        create_spinner(&msg!("answer.spinner"));
 
-    let final_request_payload = OpenRouterRequest {
-        stream: Some(true),
-        model: SUMMARY_MODEL,
-        messages: vec![Message {
-            role: "user",
-            content: &final_prompt,
-        }],
-    };
-
-    let timeout = Duration::new(300, 0);
-    let final_response_raw = http_client
-        .post(API_URL)
-        .timeout(timeout)
-        .bearer_auth(openrouter_api_key)
-        .header("HTTP-Referer", REFERER)
-        .header("X-Title", APPNAME)
-        .json(&final_request_payload)
-        .send()
+    let model = budget::admit(SUMMARY_MODEL, "Final OpenRouter Answer Generation", prompts.system(), &final_prompt)?;
+    let final_response_raw = send_chat(http_client, openrouter_api_key, &model, generation.phase(PromptName::Answer), prompts.system(), &final_prompt)
         .await
         .context("Failed to send final request to OpenRouter")?;
 
-    // final_answer_spinner.set_message("Receiving final answer from OpenRouter...");
-    // Print the message directly before starting the stream handling if stream_to_stdout is true.
-    // The handle_openrouter_stream function is called with stream_to_stdout = true for final answer.
-    println!("{}", "Receiving final answer from OpenRouter...".yellow());
+    output::detail(msg!("answer.receiving").yellow());
 
-    let (final_answer_content, usage_info) = handle_openrouter_response(
+    // The answer is streamed straight to stdout; the spinner is finished when the first chunk arrives.
+    let ChatResponse { content: final_answer_content, usage: usage_info, .. } = handle_openrouter_response(
+        http_client,
+        openrouter_api_key,
         final_response_raw,
+        &model,
         "Final OpenRouter Answer Generation",
-        false,
+        true,
         &final_answer_spinner,
     )
     .await?; // Retained original context_msg as it's for internal logging/error handling
 
-    final_answer_spinner.finish_with_message("✅ Final answer received successfully:");
+    final_answer_spinner.finish_with_message(msg!("answer.received"));
     Ok((final_answer_content, usage_info))
 }
 
+// The user prompt of the final answer request, also used to size it against the budget.
+pub fn final_answer_prompt(prompts: &Prompts, user_query: &str, search_results_summary: &str) -> Result<String> {
+    prompts.render(PromptName::Answer, &PromptVars {
+        sources: Some(search_results_summary),
+        ..prompts.vars(user_query)
+    })
+}
+
+// Suggests follow-up questions for an answered query, one per returned string.
+pub async fn generate_follow_ups(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
+    user_query: &str,
+    answer: &str,
+) -> Result<(Vec<String>, Option<UsageInfo>)> {
+    let follow_up_prompt = prompts.render(PromptName::FollowUp, &PromptVars {
+        answer: Some(answer),
+        ..prompts.vars(user_query)
+    })?;
+    let follow_up_spinner = create_spinner(&msg!("follow_ups.spinner"));
+    let model = budget::admit(SEARCH_MODEL, "OpenRouter Follow-up Questions", prompts.system(), &follow_up_prompt)?;
+    let response = send_chat(http_client, openrouter_api_key, &model, generation.phase(PromptName::FollowUp), prompts.system(), &follow_up_prompt)
+        .await
+        .context("Failed to send follow-up request to OpenRouter")?;
+    let ChatResponse { content, usage: usage_info, .. } = handle_openrouter_response(http_client, openrouter_api_key, response, &model, "OpenRouter Follow-up Questions", false, &follow_up_spinner).await?;
+
+    let questions: Vec<String> = content.lines()
+        .map(|line| line.trim().trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | ')' | '-' | '*')).trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    follow_up_spinner.finish_with_message(msg!("follow_ups.done", count = questions.len()));
+    Ok((questions, usage_info))
+}
+
+// Reviews an answer against the search results it was based on.
+pub async fn generate_critique(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
+    user_query: &str,
+    search_results_summary: &str,
+    answer: &str,
+) -> Result<(String, Option<UsageInfo>)> {
+    let critique_prompt = prompts.render(PromptName::Critique, &PromptVars {
+        sources: Some(search_results_summary),
+        answer: Some(answer),
+        ..prompts.vars(user_query)
+    })?;
+    let critique_spinner = create_spinner(&msg!("critique.spinner"));
+    let model = budget::admit(SUMMARY_MODEL, "OpenRouter Answer Critique", prompts.system(), &critique_prompt)?;
+    let response = send_chat(http_client, openrouter_api_key, &model, generation.phase(PromptName::Critique), prompts.system(), &critique_prompt)
+        .await
+        .context("Failed to send critique request to OpenRouter")?;
+    let ChatResponse { content, usage: usage_info, .. } = handle_openrouter_response(http_client, openrouter_api_key, response, &model, "OpenRouter Answer Critique", false, &critique_spinner).await?;
+    critique_spinner.finish_with_message(msg!("critique.done"));
+    Ok((content.trim().to_string(), usage_info))
+}
+
+// One step of the agent loop: the conversation so far, with the tools on offer. The reply is
+// collected rather than streamed, since only the step that answers (without tool calls) belongs
+// on stdout; tool calls are returned for the caller to run.
+pub async fn generate_agent_step(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    model: &str,
+    params: &GenerationParams,
+    messages: Vec<Message<'_>>,
+    tools: &[ToolDefinition],
+    tool_choice: &str,
+) -> Result<ChatResponse> {
+    let agent_spinner = create_spinner(&msg!("agent.spinner"));
+    let request = OpenRouterRequest {
+        tools: Some(tools),
+        tool_choice: Some(tool_choice),
+        ..chat_request(model, params, messages)
+    };
+    let response = post_chat(http_client, openrouter_api_key, &request)
+        .await
+        .context("Failed to send agent request to OpenRouter")?;
+    let chat_response = handle_openrouter_response(http_client, openrouter_api_key, response, model, "OpenRouter Agent Step", false, &agent_spinner).await?;
+    if chat_response.tool_calls.is_empty() {
+        agent_spinner.finish_with_message(msg!("agent.answered"));
+    } else {
+        agent_spinner.finish_with_message(msg!("agent.tool_calls", count = chat_response.tool_calls.len()).cyan().to_string());
+    }
+    Ok(chat_response)
+}
+
+// One attempt at a structured answer: the conversation so far (with earlier invalid replies and
+// what was wrong with them), constrained to `response_format` when the model supports it. The
+// reply is collected rather than streamed, since it is only written out once it validates.
+pub async fn generate_structured_answer(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    model: &str,
+    params: &GenerationParams,
+    messages: Vec<Message<'_>>,
+    response_format: Option<ResponseFormat<'_>>,
+) -> Result<ChatResponse> {
+    let structured_spinner = create_spinner(&msg!("structured.spinner"));
+    let request = OpenRouterRequest {
+        response_format,
+        ..chat_request(model, params, messages)
+    };
+    let response = post_chat(http_client, openrouter_api_key, &request)
+        .await
+        .context("Failed to send structured answer request to OpenRouter")?;
+    let chat_response = handle_openrouter_response(http_client, openrouter_api_key, response, model, "Structured OpenRouter Answer", false, &structured_spinner).await?;
+    structured_spinner.finish_with_message(msg!("structured.received"));
+    Ok(chat_response)
+}
+
+// Sends a streaming chat completion request: the optional system prompt, then the user prompt.
+async fn send_chat(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    model: &str,
+    params: &GenerationParams,
+    system: Option<&str>,
+    prompt: &str,
+) -> Result<reqwest::Response> {
+    let mut messages = Vec::new();
+    if let Some(system) = system {
+        messages.push(Message { role: "system", content: system, ..Default::default() });
+    }
+    messages.push(Message { role: "user", content: prompt, ..Default::default() });
+    post_chat(http_client, openrouter_api_key, &chat_request(model, params, messages)).await
+}
+
+// A streaming chat completion request for a conversation, with the phase's parameters adjusted
+// to the model and the provider preferences. Callers add tools or a response format.
+fn chat_request<'a>(model: &'a str, params: &GenerationParams, messages: Vec<Message<'a>>) -> OpenRouterRequest<'a> {
+    OpenRouterRequest {
+        model,
+        stream: Some(true),
+        messages,
+        usage: UsageRequest { include: true },
+        params: params.for_model(model),
+        provider: provider::preferences(),
+        tools: None,
+        tool_choice: None,
+        response_format: None,
+    }
+}
+
+async fn post_chat(http_client: &Client, openrouter_api_key: &str, request_payload: &OpenRouterRequest<'_>) -> Result<reqwest::Response> {
+    let timeout = Duration::new(300, 0);
+    let response = http_client
+        .post(API_URL)
+        .timeout(timeout)
+        .bearer_auth(openrouter_api_key)
+        .header("Content-Type", "application/json")
+        .header("HTTP-Referer", REFERER)
+        .header("X-Title", APPNAME)
+        .json(request_payload)
+        .send()
+        .await?;
+    Ok(response)
+}
+
+// What a streamed completion produced. Tool calls are only made when tools were offered.
+#[derive(Debug, Default)]
+pub struct ChatResponse {
+    pub content: String,
+    pub usage: Option<UsageInfo>,
+    pub tool_calls: Vec<ToolCall>,
+}
+
 // Helper function to handle OpenRouter streaming responses
 async fn handle_openrouter_response(
+    http_client: &Client,
+    openrouter_api_key: &str,
     response: reqwest::Response,
+    model: &str, // for pricing the call
     context_msg: &str,
-    stream_to_stdout: bool, // If true, prints content chunks to stdout
-    spinner: &indicatif::ProgressBar, // Pass spinner to update its message
-) -> Result<(String, Option<UsageInfo>)> {
+    stream_to_stdout: bool, // If true, renders content chunks to stdout as they arrive
+    spinner: &Spinner, // Pass spinner to update its message
+) -> Result<ChatResponse> {
     if !response.status().is_success() {
         let error_body = response
             .text()
             .await
             .unwrap_or_else(|_| "Unknown error reading response body".to_string());
-        spinner.finish_with_message(format!("❌ {} failed", context_msg)); // context_msg is already in English or a placeholder, no change needed for this specific line's user-facing part
+        spinner.finish_with_message(msg!("answer.request_failed", request = context_msg));
         return Err(anyhow::anyhow!("{}. Response: {}", context_msg, error_body));
-    } else {
     }
 
     let mut byte_stream = response.bytes_stream();
     let mut accumulated_content = String::new();
     let mut final_usage_info: Option<UsageInfo> = None;
+    let mut generation_id: Option<String> = None;
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+    // Network chunks can end mid-line (or mid-character); only complete lines are parsed.
+    let mut pending: Vec<u8> = Vec::new();
 
-    if stream_to_stdout {
-        println!(); // New line before streaming starts
-    }
+    let mut answer_stream = stream_to_stdout.then(output::AnswerStream::new);
+    // Whether a streamed answer's thinking is being shown; it ends when the content starts.
+    let mut thinking = false;
 
-    while let Some(item) = byte_stream.next().await {
+    'stream: while let Some(item) = byte_stream.next().await {
         let chunk = item.context(format!("Error reading chunk from {} stream", context_msg))?;
-        let chunk_str = std::str::from_utf8(&chunk)
-            .context(format!("Failed to decode UTF-8 chunk from {}", context_msg))?;
+        pending.extend_from_slice(&chunk);
 
-        for line in chunk_str.lines() {
-            if line.starts_with("data: ") {
-                let json_data = &line[6..];
+        while let Some(newline) = pending.iter().position(|byte| *byte == b'\n') {
+            let line_bytes: Vec<u8> = pending.drain(..=newline).collect();
+            let line = std::str::from_utf8(&line_bytes)
+                .context(format!("Failed to decode UTF-8 line from {}", context_msg))?
+                .trim_end();
+            if let Some(json_data) = line.strip_prefix("data: ") {
                 if json_data.trim() == "[DONE]" {
-                    break; // Stream finished
+                    break 'stream; // Stream finished
                 }
                 match serde_json::from_str::<OpenRouterStreamResponse>(json_data) {
                     Result::Ok(stream_resp) => {
-                        if !stream_resp.error.is_some() {
-                            eprintln!("Oi, internal server error!")
+                        if let Some(error) = stream_resp.error {
+                            spinner.finish_with_message(msg!("answer.request_failed", request = context_msg));
+                            return Err(anyhow::anyhow!("{} returned an error mid-stream: {}", context_msg, error.message));
                         } else {
+                            if generation_id.is_none() {
+                                generation_id = stream_resp.id;
+                            }
                             if let Some(usage) = stream_resp.usage {
                                 final_usage_info = Some(usage.clone());
                             }
                             for choice in stream_resp.choices {
-                                if let Some(content_delta) = choice.delta.content {
-                                    accumulated_content.push_str(&content_delta);
-                                    if stream_to_stdout {
-                                        print!("{}", content_delta);
-                                        stdout().flush().context("Failed to flush stdout")?;
+                                if let Some(reasoning_delta) = choice.delta.reasoning.filter(|delta| !delta.is_empty())
+                                    && let Some(answer_stream) = &mut answer_stream
+                                {
+                                    if !thinking {
+                                        thinking = true;
+                                        start_thinking(answer_stream, spinner)?;
                                     }
+                                    show_thinking(answer_stream, &reasoning_delta)?;
+                                }
+                                for tool_call_delta in choice.delta.tool_calls.into_iter().flatten() {
+                                    if tool_calls.len() <= tool_call_delta.index {
+                                        tool_calls.resize_with(tool_call_delta.index + 1, ToolCall::default);
+                                    }
+                                    let tool_call = &mut tool_calls[tool_call_delta.index];
+                                    if let Some(id) = tool_call_delta.id {
+                                        tool_call.id = id;
+                                        tool_call.kind = "function".to_string();
+                                    }
+                                    if let Some(function) = tool_call_delta.function {
+                                        tool_call.function.name.push_str(function.name.as_deref().unwrap_or_default());
+                                        tool_call.function.arguments.push_str(function.arguments.as_deref().unwrap_or_default());
+                                    }
+                                }
+                                if let Some(content_delta) = choice.delta.content.filter(|delta| !delta.is_empty()) {
+                                    if let Some(answer_stream) = &mut answer_stream {
+                                        if thinking {
+                                            thinking = false;
+                                            end_thinking(answer_stream)?;
+                                        }
+                                        if accumulated_content.is_empty() {
+                                            spinner.finish_with_message(msg!("answer.streaming").bright_green().bold().to_string());
+                                        }
+                                        answer_stream.write(&content_delta).context("Failed to write answer to stdout")?;
+                                    }
+                                    accumulated_content.push_str(&content_delta);
                                 }
                                 if choice.finish_reason.is_some() {
                                     // Potentially handle finish reason if needed
@@ -172,35 +386,179 @@ async fn handle_openrouter_response(
                         }
                     }
                     Err(e) => {
-                        // It's possible to get non-JSON metadata or empty lines in the stream
-                        // eprintln!(
-                        //     "Warning: Failed to parse stream data chunk from {}: {}. Chunk: '{}'",
-                        //     context_msg,
-                        //     e,
-                        //     json_data
-                        // );
-                        // For now, we'll be a bit lenient with parsing errors in individual chunks if they are not [DONE]
-                        // but if it's persistent, it indicates a problem.
+                        // Lines are complete now, so a parse failure means an unexpected payload.
                         let trimmed_json_data = json_data.trim();
                         if !trimmed_json_data.is_empty() {
                             // Log if it's not empty after trimming
-                            eprintln!(
-                                "Warning: Failed to parse stream data chunk from {}: {}. Chunk: '{}'",
-                                context_msg,
-                                e,
-                                trimmed_json_data // Log the trimmed version for clarity
-                            );
+                            output::detail(msg!("stream.bad_chunk",
+                                request = context_msg,
+                                error = e,
+                                chunk = trimmed_json_data, // Log the trimmed version for clarity
+                            ));
                         }
                     }
                 }
             } else if !line.trim().is_empty() {
-                // Potentially log other non-data lines if necessary for debugging
-                // eprintln!("Non-data line from stream: {}", line);
+                // Comment lines such as ": OPENROUTER PROCESSING" keep the connection alive.
+            }
+        }
+    }
+    if let Some(answer_stream) = &mut answer_stream {
+        if thinking {
+            end_thinking(answer_stream)?;
+        }
+        answer_stream.finish().context("Failed to write answer to stdout")?;
+    }
+    let usage = account_usage(http_client, openrouter_api_key, model, final_usage_info, generation_id).await;
+    Ok(ChatResponse { content: accumulated_content, usage, tool_calls })
+}
+
+// Thinking goes to stderr, dimmed, or (when included) into the answer output as a collapsible
+// block; the spinner stops either way, since the first content no longer finishes it.
+fn start_thinking(answer_stream: &mut output::AnswerStream, spinner: &Spinner) -> Result<()> {
+    match reasoning::display() {
+        ThinkingDisplay::Hide => spinner.set_message(msg!("answer.thinking")),
+        ThinkingDisplay::Dim => spinner.finish_with_message(msg!("answer.thinking").dimmed().to_string()),
+        ThinkingDisplay::Include => {
+            spinner.finish_with_message(msg!("answer.thinking"));
+            answer_stream.write(&format!("<details>\n<summary>{}</summary>\n\n", msg!("answer.thinking_summary")))
+                .context("Failed to write answer to stdout")?;
+        }
+    }
+    Ok(())
+}
+
+fn show_thinking(answer_stream: &mut output::AnswerStream, delta: &str) -> Result<()> {
+    match reasoning::display() {
+        ThinkingDisplay::Hide => {}
+        ThinkingDisplay::Dim => output::thinking(delta.dimmed()),
+        ThinkingDisplay::Include => answer_stream.write(delta).context("Failed to write answer to stdout")?,
+    }
+    Ok(())
+}
+
+// The spinner is already finished, so the answer heading is printed here.
+fn end_thinking(answer_stream: &mut output::AnswerStream) -> Result<()> {
+    match reasoning::display() {
+        ThinkingDisplay::Hide => return Ok(()),
+        ThinkingDisplay::Dim => output::thinking("\n\n"),
+        ThinkingDisplay::Include => answer_stream.write("\n\n</details>\n\n").context("Failed to write answer to stdout")?,
+    }
+    output::status(msg!("answer.streaming").bright_green().bold());
+    Ok(())
+}
+
+// Adds the generation id and, when enabled, the generation stats to the usage, then records the
+// call's cost. Stats also stand in for usage the stream didn't include.
+async fn account_usage(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    model: &str,
+    usage_info: Option<UsageInfo>,
+    generation_id: Option<String>,
+) -> Option<UsageInfo> {
+    let stats = match &generation_id {
+        Some(id) if cost::generation_stats_enabled() => match fetch_generation_stats(http_client, openrouter_api_key, id).await {
+            Result::Ok(stats) => Some(stats),
+            Err(e) => {
+                output::detail(msg!("usage.stats_failed", id = id, error = format!("{:#}", e)).dimmed());
+                None
+            }
+        },
+        _ => None,
+    };
+    let mut usage = match (usage_info, &stats) {
+        (Some(usage), _) => usage,
+        (None, Some(stats)) => {
+            let prompt_tokens = stats.native_tokens_prompt.unwrap_or(0);
+            let completion_tokens = stats.native_tokens_completion.unwrap_or(0);
+            UsageInfo {
+                prompt_tokens,
+                completion_tokens: Some(completion_tokens),
+                total_tokens: prompt_tokens + completion_tokens,
+                ..Default::default()
             }
         }
+        (None, None) => return None,
+    };
+    if let Some(stats) = &stats {
+        if usage.cost.is_none() {
+            usage.cost = stats.total_cost;
+        }
+        output::detail(msg!("usage.generation",
+            id = generation_id.as_deref().unwrap_or_default(),
+            provider = stats.provider_name.as_deref().unwrap_or("?"),
+            model = stats.model.as_deref().unwrap_or(model),
+            latency = stats.latency.unwrap_or(0),
+            time = stats.generation_time.unwrap_or(0),
+            prompt = stats.native_tokens_prompt.unwrap_or(0),
+            cached = stats.native_tokens_cached.unwrap_or(0),
+            completion = stats.native_tokens_completion.unwrap_or(0),
+            reasoning = stats.native_tokens_reasoning.unwrap_or(0),
+        ).dimmed());
+    } else if let Some(id) = &generation_id {
+        output::detail(msg!("usage.generation_id", id = id,
+            cached = usage.cached_tokens().unwrap_or(0), reasoning = usage.reasoning_tokens().unwrap_or(0)).dimmed());
     }
-    if stream_to_stdout {
-        println!(); // New line after streaming finishes
+    usage.generation_id = generation_id;
+    usage.stats = stats;
+    cost::record_openrouter(model, &mut usage);
+    Some(usage)
+}
+
+// Stats are written shortly after the stream ends, so a missing generation is retried briefly.
+async fn fetch_generation_stats(http_client: &Client, openrouter_api_key: &str, generation_id: &str) -> Result<GenerationStats> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let response = http_client
+            .get(GENERATION_URL)
+            .query(&[("id", generation_id)])
+            .timeout(Duration::from_secs(10))
+            .bearer_auth(openrouter_api_key)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND && attempt < GENERATION_STATS_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(500 * u64::from(attempt))).await;
+            continue;
+        }
+        let stats = response.error_for_status()?
+            .json::<GenerationStatsResponse>()
+            .await
+            .context("Failed to parse the generation stats")?;
+        return Ok(stats.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_suggestions() {
+        let cases: [(&str, &str, Option<u32>, &[&str]); 7] = [
+            (
+                r#"{"keywords": "rust async runtime", "recency_days": 30, "domains": ["tokio.rs"]}"#,
+                "rust async runtime", Some(30), &["tokio.rs"],
+            ),
+            (r#"{"keywords": "rust 2024 edition"}"#, "rust 2024 edition", None, &[]),
+            (
+                "```json\n{\"keywords\": \"tokio select\", \"domains\": [\"docs.rs\"]}\n```",
+                "tokio select", None, &["docs.rs"],
+            ),
+            ("Here you go:\n{\"keywords\": \"serde flatten\", \"recency_days\": 7}\nHope it helps.", "serde flatten", Some(7), &[]),
+            // Anything that isn't usable JSON becomes the keyword list itself.
+            ("  rust borrow checker errors\n", "rust borrow checker errors", None, &[]),
+            (r#"{"keywords": "  ", "recency_days": 30}"#, r#"{"keywords": "  ", "recency_days": 30}"#, None, &[]),
+            ("sets like {a, b} }{ ", "sets like {a, b} }{", None, &[]),
+        ];
+        for (content, keywords, recency_days, domains) in cases {
+            let suggestion = parse_keyword_suggestion(content);
+            assert_eq!(
+                (suggestion.keywords.as_str(), suggestion.recency_days, suggestion.domains),
+                (keywords, recency_days, domains.iter().map(|domain| domain.to_string()).collect::<Vec<_>>()),
+                "{:?}", content,
+            );
+        }
     }
-    Ok((accumulated_content, final_usage_info))
 }
@@ -0,0 +1,191 @@
+use clap::ValueEnum;
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use crate::markdown::{RenderOptions, StreamRenderer};
+
+// Presentation layer: decides what gets printed where.
+// Only the final answer goes to stdout; banners, spinners and diagnostics go to stderr.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug)]
+pub struct OutputSettings {
+    pub verbosity: Verbosity,
+    pub stderr_is_tty: bool,
+    pub stderr_color: bool,
+    pub render_markdown: bool,
+    pub markdown: RenderOptions,
+}
+
+static SETTINGS: OnceLock<OutputSettings> = OnceLock::new();
+
+// `raw` disables Markdown rendering of the answer. Otherwise the answer is rendered when
+// stdout is a terminal (or colors are forced), and left as plain Markdown when piped.
+pub fn init(verbosity: Verbosity, color: ColorChoice, raw: bool) {
+    let stdout_is_tty = io::stdout().is_terminal();
+    let stderr_is_tty = io::stderr().is_terminal();
+    let stdout_color = should_color(color, stdout_is_tty);
+    let settings = OutputSettings {
+        verbosity,
+        stderr_is_tty,
+        stderr_color: should_color(color, stderr_is_tty),
+        render_markdown: !raw && (stdout_is_tty || color == ColorChoice::Always),
+        markdown: RenderOptions {
+            color: stdout_color,
+            hyperlinks: stdout_color && supports_hyperlinks::on(supports_hyperlinks::Stream::Stdout),
+            width: console::Term::stdout().size_checked().map_or(80, |(_, columns)| columns as usize),
+        },
+    };
+    // Almost everything styled with `colored` ends up on stderr, so it drives the global switch.
+    colored::control::set_override(settings.stderr_color);
+    let _ = SETTINGS.set(settings);
+}
+
+pub fn settings() -> &'static OutputSettings {
+    SETTINGS.get_or_init(|| OutputSettings {
+        verbosity: Verbosity::Normal,
+        stderr_is_tty: false,
+        stderr_color: false,
+        render_markdown: false,
+        markdown: RenderOptions { color: false, hyperlinks: false, width: 80 },
+    })
+}
+
+// NO_COLOR (https://no-color.org) wins over CLICOLOR_FORCE, which wins over TTY detection.
+fn should_color(choice: ColorChoice, is_tty: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                return false;
+            }
+            if env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0") {
+                return true;
+            }
+            is_tty && !env::var("TERM").is_ok_and(|term| term == "dumb")
+        }
+    }
+}
+
+pub fn is_quiet() -> bool {
+    settings().verbosity == Verbosity::Quiet
+}
+
+pub fn is_verbose() -> bool {
+    settings().verbosity >= Verbosity::Verbose
+}
+
+// Spinners only make sense on an interactive stderr.
+pub fn spinners_enabled() -> bool {
+    !is_quiet() && settings().stderr_is_tty
+}
+
+// Phase banners, progress lines and usage summaries.
+pub fn status(message: impl Display) {
+    if !is_quiet() {
+        eprintln!("{}", message);
+    }
+}
+
+// Extra diagnostics only shown with --verbose.
+pub fn detail(message: impl Display) {
+    if is_verbose() {
+        eprintln!("{}", message);
+    }
+}
+
+pub fn warn(message: impl Display) {
+    if !is_quiet() {
+        eprintln!("{}", message);
+    }
+}
+
+// Errors are always shown, even with --quiet.
+pub fn error(message: impl Display) {
+    eprintln!("{}", message);
+}
+
+// A model's thinking, streamed to stderr as it arrives; the caller styles it and ends the line.
+pub fn thinking(delta: impl Display) {
+    if !is_quiet() {
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "{}", delta);
+        let _ = stderr.flush();
+    }
+}
+
+// A machine-readable answer (a structured JSON document), written to stdout as is.
+pub fn document(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", text)?;
+    stdout.flush()
+}
+
+// Writes a complete answer to stdout, rendering Markdown unless output is raw.
+pub fn answer(text: &str) -> io::Result<()> {
+    let mut answer_stream = AnswerStream::new();
+    answer_stream.write(text)?;
+    answer_stream.finish()
+}
+
+// The answer is the only thing written to stdout. It is written as it streams in,
+// rendering Markdown block by block unless output is raw.
+pub struct AnswerStream {
+    renderer: Option<StreamRenderer>,
+    ends_with_newline: bool,
+}
+
+impl AnswerStream {
+    pub fn new() -> Self {
+        let settings = settings();
+        Self {
+            renderer: settings.render_markdown.then(|| StreamRenderer::new(settings.markdown)),
+            ends_with_newline: true,
+        }
+    }
+
+    pub fn write(&mut self, delta: &str) -> io::Result<()> {
+        let text = match &mut self.renderer {
+            Some(renderer) => renderer.push(delta),
+            None => delta.to_string(),
+        };
+        self.write_stdout(&text)
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(renderer) = &mut self.renderer {
+            let rest = renderer.finish();
+            self.write_stdout(&rest)?;
+        }
+        if !self.ends_with_newline {
+            self.write_stdout("\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_stdout(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(text.as_bytes())?;
+        self.ends_with_newline = text.ends_with('\n');
+        stdout.flush()
+    }
+}
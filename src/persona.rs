@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Personas are system prompts that set the tone and depth of every model call (keyword
+// generation and answers alike). A few presets are built in; the config can add more.
+
+const BUILTIN_PERSONAS: &[(&str, &str)] = &[
+    (
+        "concise-engineer",
+        "You are a senior software engineer. Be concise and practical: lead with the answer, prefer code, commands and concrete numbers over prose, and leave out background the reader did not ask for.",
+    ),
+    (
+        "academic",
+        "You are a careful academic researcher. Write precise, well-structured answers, qualify uncertain or contested claims, and cite the supporting search result for every factual claim with its number and URL.",
+    ),
+    (
+        "beginner",
+        "You are a patient teacher explaining the topic to someone new to it. Define jargon the first time you use it, build up from the basics, and use simple examples and analogies.",
+    ),
+];
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PersonaOptions {
+    // Persona used when none is given on the command line.
+    pub name: Option<String>,
+    // Extra system prompt text, sent after the persona's.
+    pub system: Option<String>,
+    // Additional personas (name -> system prompt); a built-in name is replaced.
+    pub presets: BTreeMap<String, String>,
+}
+
+impl PersonaOptions {
+    // All personas by name, built-in ones first unless the config replaces them.
+    pub fn presets(&self) -> Vec<(&str, &str)> {
+        let mut presets: Vec<(&str, &str)> = BUILTIN_PERSONAS.iter()
+            .filter(|(name, _)| !self.presets.contains_key(*name))
+            .copied()
+            .collect();
+        presets.extend(self.presets.iter().map(|(name, prompt)| (name.as_str(), prompt.as_str())));
+        presets
+    }
+
+    // The system prompt for the selected persona plus any extra text, or None for neither.
+    pub fn system_prompt(&self) -> Result<Option<String>> {
+        let persona = match self.name.as_deref() {
+            Some(name) => {
+                let presets = self.presets();
+                let Some((_, prompt)) = presets.iter().find(|(preset, _)| *preset == name) else {
+                    let known = presets.iter().map(|(preset, _)| *preset).collect::<Vec<_>>().join(", ");
+                    return Err(anyhow::anyhow!("Unknown persona `{}`. Available personas: {}", name, known));
+                };
+                Some(prompt.to_string())
+            }
+            None => None,
+        };
+        let parts: Vec<String> = persona.into_iter().chain(self.system.clone()).filter(|part| !part.trim().is_empty()).collect();
+        Ok((!parts.is_empty()).then(|| parts.join("\n\n")))
+    }
+}
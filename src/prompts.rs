@@ -0,0 +1,197 @@
+use anyhow::{Context, Result, Ok};
+use clap::ValueEnum;
+use minijinja::{Environment, UndefinedBehavior};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::constants::app::APP_DIR_NAME;
+use crate::constants::prompts::{DIR_NAME, FILE_EXTENSION};
+use crate::language;
+use crate::locale::LocalContext;
+
+// Named prompt templates (minijinja). The defaults are compiled into the binary; a file with
+// the same name in the prompts directory overrides one, so teams can customize prompts
+// without forking.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PromptName {
+    Keyword,
+    Answer,
+    FollowUp,
+    Critique,
+    Agent,
+    Structured,
+}
+
+impl PromptName {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromptName::Keyword => "keyword",
+            PromptName::Answer => "answer",
+            PromptName::FollowUp => "follow-up",
+            PromptName::Critique => "critique",
+            PromptName::Agent => "agent",
+            PromptName::Structured => "structured",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            PromptName::Keyword => "Turns the question into search keywords and search hints (JSON)",
+            PromptName::Answer => "Answers the question from the search results",
+            PromptName::FollowUp => "Suggests follow-up questions after the answer (--follow-ups)",
+            PromptName::Critique => "Reviews the answer against the search results (--critique)",
+            PromptName::Agent => "Researches the question with search tools, then answers it (--agent)",
+            PromptName::Structured => "Answers from the search results as JSON matching a schema (--schema)",
+        }
+    }
+
+    pub fn default_source(self) -> &'static str {
+        match self {
+            PromptName::Keyword => include_str!("../prompts/keyword.jinja"),
+            PromptName::Answer => include_str!("../prompts/answer.jinja"),
+            PromptName::FollowUp => include_str!("../prompts/follow-up.jinja"),
+            PromptName::Critique => include_str!("../prompts/critique.jinja"),
+            PromptName::Agent => include_str!("../prompts/agent.jinja"),
+            PromptName::Structured => include_str!("../prompts/structured.jinja"),
+        }
+    }
+
+    fn file_name(self) -> String {
+        format!("{}.{}", self.as_str(), FILE_EXTENSION)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptOptions {
+    // Defaults to <config dir>/llm-search/prompts.
+    pub dir: Option<PathBuf>,
+}
+
+// Everything a template can use. Fields that don't apply to a prompt are empty.
+#[derive(Serialize, Debug, Default)]
+pub struct PromptVars<'a> {
+    pub query: &'a str,
+    pub date: String,
+    pub timezone: String,
+    pub locale: String,
+    pub sources: Option<&'a str>,
+    pub language: Option<String>,
+    pub answer: Option<&'a str>,
+    // The JSON Schema a structured answer must match.
+    pub schema: Option<&'a str>,
+}
+
+pub struct Prompts {
+    dir: Option<PathBuf>,
+    // Persona system prompt sent ahead of every rendered prompt.
+    system: Option<String>,
+    local: LocalContext,
+    // Answer language setting (see LanguageOptions::answer), resolved per query.
+    answer_language: Option<String>,
+}
+
+impl Prompts {
+    pub fn new(options: &PromptOptions) -> Self {
+        let dir = options.dir.clone()
+            .or_else(|| dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join(DIR_NAME)));
+        Self { dir, system: None, local: LocalContext::system(), answer_language: None }
+    }
+
+    pub fn with_system(mut self, system: Option<String>) -> Self {
+        self.system = system;
+        self
+    }
+
+    pub fn system(&self) -> Option<&str> {
+        self.system.as_deref()
+    }
+
+    pub fn with_local(mut self, local: LocalContext) -> Self {
+        self.local = local;
+        self
+    }
+
+    pub fn local(&self) -> &LocalContext {
+        &self.local
+    }
+
+    pub fn with_answer_language(mut self, answer_language: Option<String>) -> Self {
+        self.answer_language = answer_language;
+        self
+    }
+
+    // Variables for a prompt about `query`, with the date, time zone, locale and answer
+    // language filled in.
+    pub fn vars<'a>(&self, query: &'a str) -> PromptVars<'a> {
+        PromptVars {
+            query,
+            date: self.local.date(),
+            timezone: self.local.timezone(),
+            locale: self.local.locale.clone(),
+            language: self.answer_language.as_deref().and_then(|spec| language::resolve(spec, query)),
+            ..Default::default()
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    // Path of the user's override file for `name`, whether or not it exists.
+    pub fn override_path(&self, name: PromptName) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(name.file_name()))
+    }
+
+    pub fn is_overridden(&self, name: PromptName) -> bool {
+        self.override_path(name).is_some_and(|path| path.is_file())
+    }
+
+    // The template text in effect: the override file when there is one, else the default.
+    pub fn source(&self, name: PromptName) -> Result<Cow<'static, str>> {
+        match self.override_path(name).filter(|path| path.is_file()) {
+            Some(path) => {
+                let source = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read prompt template {}", path.display()))?;
+                Ok(Cow::Owned(source))
+            }
+            None => Ok(Cow::Borrowed(name.default_source())),
+        }
+    }
+
+    pub fn render(&self, name: PromptName, vars: &PromptVars) -> Result<String> {
+        render_source(name, &self.source(name)?, vars)
+    }
+
+    // Parses and test-renders a template, so `prompts edit` can report mistakes right away.
+    pub fn check(source: &str, name: PromptName) -> Result<()> {
+        let sample = PromptVars {
+            query: "example question",
+            date: "2025-01-01".to_string(),
+            timezone: "UTC (UTC+00:00)".to_string(),
+            locale: "en-US".to_string(),
+            sources: Some("Result 1: ..."),
+            language: Some("English".to_string()),
+            answer: Some("example answer"),
+            schema: Some("{\"type\": \"object\"}"),
+        };
+        render_source(name, source, &sample)?;
+        Ok(())
+    }
+}
+
+fn render_source(name: PromptName, source: &str, vars: &PromptVars) -> Result<String> {
+    environment()
+        .render_named_str(&name.file_name(), source, vars)
+        .with_context(|| format!("Failed to render prompt template `{}`", name.as_str()))
+}
+
+fn environment() -> Environment<'static> {
+    let mut environment = Environment::new();
+    // Misspelled variables should fail loudly instead of silently rendering as empty.
+    environment.set_undefined_behavior(UndefinedBehavior::Strict);
+    environment
+}
@@ -0,0 +1,122 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// OpenRouter provider routing: which upstream providers may serve a request, in what order,
+// and under which data policy. Sent as the `provider` object of every chat request.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DataCollection {
+    Allow,
+    // Only providers that don't store or train on prompts.
+    Deny,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderSort {
+    Price,
+    Throughput,
+    Latency,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    Int4,
+    Int8,
+    Fp4,
+    Fp6,
+    Fp8,
+    Fp16,
+    Bf16,
+    Fp32,
+    Unknown,
+}
+
+// USD per million tokens; providers charging more are skipped.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaxPrice {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderPreferences {
+    // Providers to try first, in order, e.g. ["anthropic", "openai"].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    // Whether providers outside `order` may serve the request when those fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    // Only use providers that support every parameter in the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_parameters: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<DataCollection>,
+    // Only zero-data-retention endpoints.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zdr: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quantizations: Vec<Quantization>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ProviderSort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<MaxPrice>,
+}
+
+impl ProviderPreferences {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // e.g. "data collection denied, zero data retention, order: anthropic, openai".
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(data_collection) = self.data_collection {
+            parts.push(match data_collection {
+                DataCollection::Allow => "data collection allowed".to_string(),
+                DataCollection::Deny => "data collection denied".to_string(),
+            });
+        }
+        if self.zdr == Some(true) {
+            parts.push("zero data retention".to_string());
+        }
+        if !self.order.is_empty() {
+            parts.push(format!("order: {}", self.order.join(", ")));
+        }
+        if !self.only.is_empty() {
+            parts.push(format!("only: {}", self.only.join(", ")));
+        }
+        if !self.ignore.is_empty() {
+            parts.push(format!("ignoring: {}", self.ignore.join(", ")));
+        }
+        if self.allow_fallbacks == Some(false) {
+            parts.push("no fallbacks".to_string());
+        }
+        if let Some(sort) = self.sort {
+            parts.push(format!("sorted by {}", sort.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default()));
+        }
+        parts.join(", ")
+    }
+}
+
+static PREFERENCES: OnceLock<ProviderPreferences> = OnceLock::new();
+
+pub fn init(preferences: ProviderPreferences) {
+    let _ = PREFERENCES.set(preferences);
+}
+
+// The preferences to send with each request, if any are set.
+pub fn preferences() -> Option<&'static ProviderPreferences> {
+    PREFERENCES.get().filter(|preferences| !preferences.is_empty())
+}
@@ -0,0 +1,70 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Reasoning ("thinking") models: how much they may think, set per phase through the generation
+// parameters, and how their thinking is shown. Thinking never becomes part of the answer text.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+// The `reasoning` object of a request. Effort and max_tokens are alternatives.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReasoningParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    // Think, but don't send the thinking back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ThinkingDisplay {
+    // Stream the answer's thinking to stderr, dimmed, before the answer.
+    Dim,
+    // Don't show it, and ask models that are configured to reason not to send it.
+    Hide,
+    // Also write it to stdout ahead of the answer, in a collapsible <details> block.
+    Include,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReasoningOptions {
+    pub display: ThinkingDisplay,
+}
+
+impl Default for ReasoningOptions {
+    fn default() -> Self {
+        Self { display: ThinkingDisplay::Dim }
+    }
+}
+
+static DISPLAY: OnceLock<ThinkingDisplay> = OnceLock::new();
+
+pub fn init(options: &ReasoningOptions) {
+    let _ = DISPLAY.set(options.display);
+}
+
+pub fn display() -> ThinkingDisplay {
+    DISPLAY.get().copied().unwrap_or(ThinkingDisplay::Dim)
+}
@@ -0,0 +1,81 @@
+use anyhow::{Result, Ok};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::constants::embeddings::PASSAGE_TOKENS;
+use crate::context::truncate_to_tokens;
+use crate::embeddings::{EmbeddingOptions, cosine_similarity, embed};
+use crate::exa_client::SearchSource;
+use crate::messages::msg;
+use crate::utils::create_spinner;
+
+// Optional stage between retrieval and the prompt: embeds the query and each result, then
+// reorders the results by cosine similarity and drops those below a threshold.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RerankOptions {
+    pub enabled: bool,
+    // Minimum cosine similarity to keep a result. What counts as similar depends on the model.
+    pub threshold: f32,
+    // Keep at most this many results after reordering.
+    pub max_results: Option<usize>,
+}
+
+impl Default for RerankOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.25,
+            max_results: None,
+        }
+    }
+}
+
+// Reorders `sources` in place; on error they are left untouched. The best result is always
+// kept, even below the threshold, so there is something to answer from.
+pub async fn rerank_sources(
+    http_client: &Client,
+    embedding_options: &EmbeddingOptions,
+    options: &RerankOptions,
+    query: &str,
+    sources: &mut Vec<SearchSource>,
+) -> Result<()> {
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let spinner = create_spinner(&msg!("rerank.spinner", count = sources.len()).yellow().to_string());
+    let mut inputs = vec![query.to_string()];
+    inputs.extend(sources.iter().map(passage));
+    let embeddings = match embed(http_client, embedding_options, &inputs).await {
+        Result::Ok(embeddings) => embeddings,
+        Err(e) => {
+            spinner.finish_with_message(msg!("rerank.spinner_failed").red().to_string());
+            return Err(e);
+        }
+    };
+    let (query_embedding, passage_embeddings) = embeddings.split_first().unwrap();
+
+    let total = sources.len();
+    let mut scored: Vec<(f32, SearchSource)> = passage_embeddings.iter()
+        .map(|embedding| cosine_similarity(query_embedding, embedding))
+        .zip(sources.drain(..))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let keep = scored.iter().filter(|(similarity, _)| *similarity >= options.threshold).count()
+        .max(1)
+        .min(options.max_results.unwrap_or(usize::MAX).max(1));
+    sources.extend(scored.into_iter().take(keep).map(|(similarity, source)| SearchSource { similarity: Some(similarity), ..source }));
+
+    spinner.finish_with_message(msg!("rerank.done", count = sources.len(), total = total, threshold = options.threshold).green().to_string());
+    Ok(())
+}
+
+// What gets embedded for a result: its title and the most condensed content available.
+fn passage(source: &SearchSource) -> String {
+    let content = source.summary.clone()
+        .or_else(|| (!source.highlights.is_empty()).then(|| source.highlights.join("\n")))
+        .or_else(|| source.text.clone())
+        .unwrap_or_else(|| source.url.clone());
+    truncate_to_tokens(&format!("{}\n{}", source.title, content), PASSAGE_TOKENS)
+}
@@ -0,0 +1,230 @@
+use anyhow::{Context, Result, Ok};
+use colored::Colorize;
+use jsonschema::Validator;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::budget;
+use crate::constants::open_router::SUMMARY_MODEL;
+use crate::cost;
+use crate::generation::GenerationOptions;
+use crate::messages::msg;
+use crate::models::{JsonSchemaFormat, Message, ResponseFormat, UsageInfo};
+use crate::openrouter_client::generate_structured_answer;
+use crate::output;
+use crate::prompts::{PromptName, PromptVars, Prompts};
+
+// Structured answers for automations: instead of prose, the answer is a JSON document matching
+// a user-supplied JSON Schema. The schema is sent as `response_format` where the model supports
+// it, every reply is validated locally, and invalid ones are sent back with their validation
+// errors for repair.
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StructuredOptions {
+    // JSON Schema file. Setting it switches answers to JSON documents.
+    pub schema: Option<PathBuf>,
+    // Ask providers to enforce the schema exactly. OpenAI's strict mode only accepts schemas
+    // where every property is required and `additionalProperties` is false.
+    pub strict: bool,
+    // Requests to fix an invalid reply before giving up.
+    pub max_repairs: u32,
+}
+
+impl Default for StructuredOptions {
+    fn default() -> Self {
+        Self { schema: None, strict: false, max_repairs: 2 }
+    }
+}
+
+// Validation errors sent back with one repair request; the rest usually follow from these.
+const MAX_REPORTED_ERRORS: usize = 10;
+
+const REPAIR_PROMPT: &str =
+    "Your reply does not match the JSON Schema. Fix these problems and reply with the corrected JSON document only:";
+
+pub struct Schema {
+    // `response_format` needs a name: the schema's title, else the file name.
+    name: String,
+    value: Value,
+    // Pretty-printed for the prompt.
+    text: String,
+    validator: Validator,
+    strict: bool,
+    max_repairs: u32,
+}
+
+// How much of the format a model can be asked to enforce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatSupport {
+    Schema,
+    JsonObject,
+    PromptOnly,
+}
+
+impl Schema {
+    fn load(path: &Path, options: &StructuredOptions) -> Result<Self> {
+        let raw = fs::read_to_string(path).with_context(|| format!("Failed to read JSON Schema {}", path.display()))?;
+        let value: Value = serde_json::from_str(&raw).with_context(|| format!("JSON Schema {} is not valid JSON", path.display()))?;
+        let validator = jsonschema::validator_for(&value)
+            .map_err(|e| anyhow::anyhow!("{} is not a valid JSON Schema: {}", path.display(), e))?;
+        let title = value.get("title").and_then(Value::as_str).map(str::to_string)
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        // OpenAI-compatible APIs accept letters, digits, `_` and `-`, up to 64 characters.
+        let name: String = title.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .take(64)
+            .collect();
+        Ok(Self {
+            name: if name.is_empty() { "answer".to_string() } else { name },
+            text: serde_json::to_string_pretty(&value)?,
+            value,
+            validator,
+            strict: options.strict,
+            max_repairs: options.max_repairs,
+        })
+    }
+
+    // The document in a reply, or everything wrong with it.
+    fn check(&self, reply: &str) -> Result<Value, Vec<String>> {
+        let document: Value = serde_json::from_str(extract_json(reply))
+            .map_err(|e| vec![format!("The reply is not valid JSON: {}", e)])?;
+        let errors: Vec<String> = self.validator.iter_errors(&document)
+            .map(|error| match error.instance_path().to_string() {
+                path if path.is_empty() => error.to_string(),
+                path => format!("at {}: {}", path, error),
+            })
+            .collect();
+        if errors.is_empty() { Result::Ok(document) } else { Err(errors) }
+    }
+
+    fn response_format(&self, support: FormatSupport) -> Option<ResponseFormat<'_>> {
+        match support {
+            FormatSupport::Schema => Some(ResponseFormat {
+                kind: "json_schema",
+                json_schema: Some(JsonSchemaFormat { name: &self.name, strict: self.strict, schema: &self.value }),
+            }),
+            FormatSupport::JsonObject => Some(ResponseFormat { kind: "json_object", json_schema: None }),
+            FormatSupport::PromptOnly => None,
+        }
+    }
+}
+
+// JSON Schema outputs where the model lists them (or nothing is known about it), plain JSON
+// mode where it only has `response_format`, and just the prompt otherwise.
+fn format_support(model: &str) -> FormatSupport {
+    let supports = |name: &str| cost::capabilities(model).is_none_or(|capabilities| {
+        capabilities.supported_parameters.is_empty() || capabilities.supported_parameters.iter().any(|supported| supported == name)
+    });
+    if supports("structured_outputs") {
+        FormatSupport::Schema
+    } else if supports("response_format") {
+        FormatSupport::JsonObject
+    } else {
+        FormatSupport::PromptOnly
+    }
+}
+
+// Models sometimes wrap the document in a code fence despite being told not to.
+fn extract_json(reply: &str) -> &str {
+    let reply = reply.trim();
+    let Some(fenced) = reply.strip_prefix("```") else { return reply };
+    let body = fenced.split_once('\n').map_or("", |(_, body)| body).trim_end();
+    body.strip_suffix("```").unwrap_or(body).trim()
+}
+
+fn repair_prompt(errors: &[String]) -> String {
+    let listed: Vec<String> = errors.iter().take(MAX_REPORTED_ERRORS).map(|error| format!("- {}", error)).collect();
+    format!("{}\n{}", REPAIR_PROMPT, listed.join("\n"))
+}
+
+static SCHEMA: OnceLock<Option<Schema>> = OnceLock::new();
+
+// Reads and compiles the schema file up front, so a bad schema fails before any search is paid for.
+pub fn init(options: &StructuredOptions) -> Result<()> {
+    let schema = match &options.schema {
+        Some(path) => Some(Schema::load(path, options)?),
+        None => None,
+    };
+    let _ = SCHEMA.set(schema);
+    Ok(())
+}
+
+// The schema answers must match, when structured output is on.
+pub fn schema() -> Option<&'static Schema> {
+    SCHEMA.get().and_then(Option::as_ref)
+}
+
+pub struct StructuredAnswer {
+    pub document: Value,
+    // Summed over the repair attempts.
+    pub usage: Option<UsageInfo>,
+}
+
+// Asks for the answer as a JSON document, sending invalid replies back with their validation
+// errors until one validates or the repairs run out.
+pub async fn answer(
+    http_client: &Client,
+    openrouter_api_key: &str,
+    prompts: &Prompts,
+    generation: &GenerationOptions,
+    schema: &Schema,
+    user_query: &str,
+    search_results_summary: &str,
+) -> Result<StructuredAnswer> {
+    let prompt = prompts.render(PromptName::Structured, &PromptVars {
+        sources: Some(search_results_summary),
+        schema: Some(&schema.text),
+        ..prompts.vars(user_query)
+    })?;
+    let mut turns: Vec<(&str, String)> = Vec::new();
+    if let Some(system) = prompts.system() {
+        turns.push(("system", system.to_string()));
+    }
+    turns.push(("user", prompt));
+    let mut usage: Option<UsageInfo> = None;
+    let mut attempts = 0;
+
+    loop {
+        let conversation: String = turns.iter().map(|(_, content)| content.as_str()).collect::<Vec<_>>().join("\n");
+        let model = budget::admit(SUMMARY_MODEL, "Structured OpenRouter Answer", None, &conversation)?;
+        let support = format_support(&model);
+        if attempts == 0 {
+            match support {
+                FormatSupport::Schema => {}
+                FormatSupport::JsonObject => output::warn(msg!("structured.json_mode", model = model).yellow()),
+                FormatSupport::PromptOnly => output::warn(msg!("structured.prompt_only", model = model).yellow()),
+            }
+        }
+        let messages = turns.iter().map(|(role, content)| Message { role, content, ..Default::default() }).collect();
+        let response = generate_structured_answer(
+            http_client, openrouter_api_key, &model, generation.phase(PromptName::Structured), messages, schema.response_format(support),
+        ).await?;
+        if let Some(step_usage) = &response.usage {
+            usage.get_or_insert_with(UsageInfo::default).add(step_usage);
+        }
+        attempts += 1;
+
+        let errors = match schema.check(&response.content) {
+            Result::Ok(document) => {
+                output::status(msg!("structured.valid", attempts = attempts).green());
+                return Ok(StructuredAnswer { document, usage });
+            }
+            Err(errors) => errors,
+        };
+        output::warn(msg!("structured.invalid", attempt = attempts, count = errors.len(), first = errors[0]).yellow());
+        for error in errors.iter().skip(1).take(MAX_REPORTED_ERRORS - 1) {
+            output::detail(format!("  {}", error).dimmed());
+        }
+        if attempts > schema.max_repairs {
+            return Err(anyhow::anyhow!("{}", msg!("structured.failed", attempts = attempts, errors = errors.join("; "))));
+        }
+        turns.push(("assistant", response.content));
+        turns.push(("user", repair_prompt(&errors)));
+    }
+}
@@ -0,0 +1,196 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+// Rule-based detection of temporal intent ("latest", "this week", "过去 3 天") in a question,
+// turned into the earliest publication date worth searching.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recency {
+    pub since: NaiveDate,
+    // The words of the question that asked for it.
+    pub cue: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Window {
+    Days(u64),
+    Months(u32),
+    // Since January 1st of the current year.
+    YearToDate,
+}
+
+// Phrases naming a fixed period, checked before the vaguer cues below.
+const PERIOD_PHRASES: &[(&str, Window)] = &[
+    ("today", Window::Days(1)),
+    ("tonight", Window::Days(1)),
+    ("yesterday", Window::Days(2)),
+    ("this week", Window::Days(7)),
+    ("last week", Window::Days(14)),
+    ("past week", Window::Days(7)),
+    ("this month", Window::Months(1)),
+    ("last month", Window::Months(2)),
+    ("past month", Window::Months(1)),
+    ("this year", Window::YearToDate),
+    ("past year", Window::Months(12)),
+    ("今天", Window::Days(1)),
+    ("今日", Window::Days(1)),
+    ("昨天", Window::Days(2)),
+    ("本周", Window::Days(7)),
+    ("这周", Window::Days(7)),
+    ("上周", Window::Days(14)),
+    ("本月", Window::Months(1)),
+    ("这个月", Window::Months(1)),
+    ("上个月", Window::Months(2)),
+    ("今年", Window::YearToDate),
+];
+
+// Words asking for current information without naming a period.
+const RECENT_CUES: &[(&str, Window)] = &[
+    ("breaking", Window::Days(7)),
+    ("recent", Window::Months(6)),
+    ("recently", Window::Months(6)),
+    ("latest", Window::Months(12)),
+    ("newest", Window::Months(12)),
+    ("current", Window::Months(12)),
+    ("currently", Window::Months(12)),
+    ("up to date", Window::Months(12)),
+    ("nowadays", Window::Months(12)),
+    ("最近", Window::Months(6)),
+    ("近期", Window::Months(6)),
+    ("最新", Window::Months(12)),
+    ("目前", Window::Months(12)),
+    ("当前", Window::Months(12)),
+    ("现在", Window::Months(12)),
+];
+
+pub fn detect_recency(query: &str, today: NaiveDate) -> Option<Recency> {
+    let lower = query.to_lowercase();
+    // English phrases must match whole words, so "current" does not match "currency".
+    let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric() && c != '-').filter(|w| !w.is_empty()).collect();
+    let padded = format!(" {} ", words.join(" ").replace('-', " "));
+    let contains = |phrase: &str| {
+        if phrase.is_ascii() { padded.contains(&format!(" {} ", phrase)) } else { lower.contains(phrase) }
+    };
+
+    let (cue, window) = explicit_window(&words).or_else(|| explicit_window_zh(&lower)).or_else(|| {
+        PERIOD_PHRASES.iter().chain(RECENT_CUES)
+            .find(|(phrase, _)| contains(phrase))
+            .map(|(phrase, window)| (phrase.to_string(), *window))
+    })?;
+    let since = match window {
+        Window::Days(days) => today.checked_sub_days(Days::new(days))?,
+        Window::Months(months) => today.checked_sub_months(Months::new(months))?,
+        Window::YearToDate => NaiveDate::from_ymd_opt(today.year(), 1, 1)?,
+    };
+    Some(Recency { since, cue })
+}
+
+// "past 3 days", "last 2 weeks", "in the last 6 months", "last 24 hours".
+fn explicit_window(words: &[&str]) -> Option<(String, Window)> {
+    words.windows(3).find_map(|w| {
+        if !matches!(w[0], "past" | "last" | "previous") {
+            return None;
+        }
+        let n: u32 = w[1].parse().ok().filter(|n| *n > 0)?;
+        Some((w.join(" "), window_for(n, w[2].trim_end_matches('s'))?))
+    })
+}
+
+// "过去3天", "近 2 周", "最近6个月".
+fn explicit_window_zh(query: &str) -> Option<(String, Window)> {
+    for prefix in ["过去", "最近", "近"] {
+        for (start, _) in query.match_indices(prefix) {
+            let rest = query[start + prefix.len()..].trim_start();
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            let Some(n) = digits.parse::<u32>().ok().filter(|n| *n > 0) else { continue };
+            let unit = rest[digits.len()..].trim_start();
+            let found = ["小时", "天", "日", "周", "星期", "个月", "月", "年"].into_iter().find(|unit_name| unit.starts_with(unit_name));
+            let Some(unit_name) = found else { continue };
+            let unit_en = match unit_name {
+                "小时" => "hour",
+                "天" | "日" => "day",
+                "周" | "星期" => "week",
+                "个月" | "月" => "month",
+                _ => "year",
+            };
+            let cue = format!("{}{}{}", prefix, n, unit_name);
+            if let Some(window) = window_for(n, unit_en) {
+                return Some((cue, window));
+            }
+        }
+    }
+    None
+}
+
+fn window_for(n: u32, unit: &str) -> Option<Window> {
+    match unit {
+        "hour" | "hr" => Some(Window::Days(u64::from(n).div_ceil(24))),
+        "day" => Some(Window::Days(u64::from(n))),
+        "week" | "wk" => Some(Window::Days(u64::from(n) * 7)),
+        "month" => Some(Window::Months(n)),
+        "year" | "yr" => Some(Window::Months(n.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn detect(query: &str) -> Option<(NaiveDate, String)> {
+        detect_recency(query, date(2024, 6, 15)).map(|recency| (recency.since, recency.cue))
+    }
+
+    #[test]
+    fn english_cues() {
+        let cases = [
+            ("What happened today?", Some((date(2024, 6, 14), "today"))),
+            ("news from this week", Some((date(2024, 6, 8), "this week"))),
+            ("What changed in the past 3 days?", Some((date(2024, 6, 12), "past 3 days"))),
+            ("releases in the last 2 weeks", Some((date(2024, 6, 1), "last 2 weeks"))),
+            ("outages in the last 24 hours", Some((date(2024, 6, 14), "last 24 hours"))),
+            ("prices over the previous 6 months", Some((date(2023, 12, 15), "previous 6 months"))),
+            ("best laptops this year", Some((date(2024, 1, 1), "this year"))),
+            ("Latest Rust release", Some((date(2023, 6, 15), "latest"))),
+            ("Is this up-to-date?", Some((date(2023, 6, 15), "up to date"))),
+            ("current interest rates", Some((date(2023, 6, 15), "current"))),
+            // Whole words only.
+            ("currency exchange history", None),
+            ("the last weekend of summer camp", None),
+            ("who has the recentest results", None),
+            ("History of the Roman Empire", None),
+            // A zero-length window is no window.
+            ("in the past 0 days", None),
+        ];
+        for (query, expected) in cases {
+            let expected = expected.map(|(since, cue)| (since, cue.to_string()));
+            assert_eq!(detect(query), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn chinese_cues() {
+        let cases = [
+            ("今天的新闻", Some((date(2024, 6, 14), "今天"))),
+            ("过去3天的股价", Some((date(2024, 6, 12), "过去3天"))),
+            ("过去 3 天的股价", Some((date(2024, 6, 12), "过去3天"))),
+            ("近 2 周的天气", Some((date(2024, 6, 1), "近2周"))),
+            // "最近" is checked before the "近" inside it.
+            ("最近2周有什么新闻", Some((date(2024, 6, 1), "最近2周"))),
+            ("最近6个月的销量", Some((date(2023, 12, 15), "最近6个月"))),
+            ("过去 48 小时", Some((date(2024, 6, 13), "过去48小时"))),
+            // Without a number, "最近" is a vague cue.
+            ("最近的新闻", Some((date(2023, 12, 15), "最近"))),
+            ("最近的 3 天", Some((date(2023, 12, 15), "最近"))),
+            ("今年的发布会", Some((date(2024, 1, 1), "今年"))),
+            ("罗马帝国的历史", None),
+        ];
+        for (query, expected) in cases {
+            let expected = expected.map(|(since, cue)| (since, cue.to_string()));
+            assert_eq!(detect(query), expected, "{}", query);
+        }
+    }
+}
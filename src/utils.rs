@@ -1,9 +1,42 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::cell::Cell;
 use std::time::Duration;
 
+use crate::output;
+
 // UI helper
-pub fn create_spinner(message: &str) -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
+// Wraps an indicatif spinner on stderr. When stderr is not a terminal (or --quiet is set)
+// the spinner is not drawn and only its final message is printed as a plain status line.
+// Only the first finish takes effect, so a spinner can be finished early (e.g. when an answer
+// starts streaming) without the later "done" message redrawing over the output.
+pub struct Spinner {
+    bar: Option<ProgressBar>,
+    finished: Cell<bool>,
+}
+
+impl Spinner {
+    pub fn set_message(&self, message: impl Into<String>) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(message.into());
+        }
+    }
+
+    pub fn finish_with_message(&self, message: impl Into<String>) {
+        if self.finished.replace(true) {
+            return;
+        }
+        match &self.bar {
+            Some(bar) => bar.finish_with_message(message.into()),
+            None => output::status(message.into()),
+        }
+    }
+}
+
+pub fn create_spinner(message: &str) -> Spinner {
+    if !output::spinners_enabled() {
+        return Spinner { bar: None, finished: Cell::new(false) };
+    }
+    let pb = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
     pb.enable_steady_tick(Duration::from_millis(120));
     pb.set_style(
         ProgressStyle::with_template("{spinner:.blue} {msg}")
@@ -19,5 +52,31 @@ pub fn create_spinner(message: &str) -> ProgressBar {
             ]),
     );
     pb.set_message(message.to_string());
-    pb
-}
\ No newline at end of file
+    Spinner { bar: Some(pb), finished: Cell::new(false) }
+}
+
+// 64-bit FNV-1a: stable across Rust versions and runs, unlike `DefaultHasher`.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
+
+// A fresh directory under the system temp dir for tests that touch files, removed when dropped.
+#[cfg(test)]
+pub struct TempDir(pub std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("llm-search-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("temp dir is writable");
+        Self(dir)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}